@@ -13,19 +13,12 @@ pub struct App {
 
 impl App {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let system_shell = std::env::var("SHELL")
-            .expect("SHELL variable is not defined")
-            .to_string();
-
         let (pty_proxy_sender, pty_proxy_receiver) = std::sync::mpsc::channel();
         let terminal_backend = TerminalBackend::new(
             0,
             cc.egui_ctx.clone(),
             pty_proxy_sender.clone(),
-            egui_term::BackendSettings {
-                shell: system_shell,
-                args: vec![],
-            },
+            egui_term::BackendSettings::default(),
         )
         .unwrap();
 
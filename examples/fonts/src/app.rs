@@ -55,19 +55,13 @@ pub struct App {
 impl App {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         setup_font(&cc.egui_ctx, TERM_FONT_JET_BRAINS_NAME);
-        let system_shell = std::env::var("SHELL")
-            .expect("SHELL variable is not defined")
-            .to_string();
 
         let (pty_proxy_sender, pty_proxy_receiver) = std::sync::mpsc::channel();
         let terminal_backend = TerminalBackend::new(
             0,
             cc.egui_ctx.clone(),
             pty_proxy_sender.clone(),
-            egui_term::BackendSettings {
-                shell: system_shell,
-                args: vec![],
-            },
+            egui_term::BackendSettings::default(),
         )
         .unwrap();
 
@@ -117,6 +111,7 @@ impl eframe::App for App {
                 .set_focus(true)
                 .set_font(TerminalFont::new(FontSettings {
                     font_type: FontId::proportional(self.font_size),
+                    ..Default::default()
                 }))
                 .set_size(Vec2::new(
                     ui.available_width(),
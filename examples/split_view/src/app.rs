@@ -65,6 +65,7 @@ impl egui_dock::TabViewer for TabViewer<'_> {
             .set_focus(true)
             .set_font(TerminalFont::new(FontSettings {
                 font_type: FontId::monospace(20f32),
+                ..Default::default()
             }))
             .set_size(ui.available_size());
         ui.add(terminal);
@@ -27,16 +27,11 @@ impl eframe::App for App {
             self.tab_manager.clear();
         }
 
-        if let Ok((tab_id, event)) = self.command_receiver.try_recv() {
-            match event {
-                egui_term::PtyEvent::Exit => {
-                    self.tab_manager.remove(tab_id);
-                },
-                egui_term::PtyEvent::Title(title) => {
-                    self.tab_manager.set_title(tab_id, title);
-                },
-                _ => {},
-            }
+        // Exited shells stay open with an overlay to restart them.
+        if let Ok((tab_id, egui_term::PtyEvent::Title(title))) =
+            self.command_receiver.try_recv()
+        {
+            self.tab_manager.set_title(tab_id, title);
         }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -58,6 +53,12 @@ impl eframe::App for App {
                     self.tab_manager
                         .add(self.command_sender.clone(), ctx.clone());
                 }
+
+                if ui.button("[x]").clicked() {
+                    if let Some(id) = self.tab_manager.active_tab_id {
+                        self.tab_manager.remove(id);
+                    }
+                }
             });
         });
 
@@ -65,7 +66,8 @@ impl eframe::App for App {
             if let Some(tab) = self.tab_manager.get_active() {
                 let terminal = TerminalView::new(ui, &mut tab.backend)
                     .set_focus(true)
-                    .set_size(ui.available_size());
+                    .set_size(ui.available_size())
+                    .set_exit_overlay(true);
 
                 ui.add(terminal);
             }
@@ -159,18 +161,11 @@ impl Tab {
         command_sender: Sender<(u64, PtyEvent)>,
         id: u64,
     ) -> Self {
-        let system_shell = std::env::var("SHELL")
-            .expect("SHELL variable is not defined")
-            .to_string();
-
         let backend = TerminalBackend::new(
             id,
             ctx,
             command_sender,
-            egui_term::BackendSettings {
-                shell: system_shell,
-                args: vec![],
-            },
+            egui_term::BackendSettings::default(),
         )
         .unwrap();
 
@@ -0,0 +1,93 @@
+use super::link::URL_REGEX;
+use alacritty_terminal::index::Point;
+
+const PATH_REGEX: &str = r"(~|\.\.?)?(/[[:alnum:]_.\-]+)+/?";
+const GIT_HASH_REGEX: &str = r"(?-u:\b)[0-9a-f]{7,40}(?-u:\b)";
+const IPV4_REGEX: &str = r"([0-9]{1,3}\.){3}[0-9]{1,3}";
+
+/// Characters used to build hint labels, home row first.
+const HINT_ALPHABET: &str = "jfkdlsahgurieowpq";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HintAction {
+    /// Open the matched text with the system handler.
+    Open,
+    /// Copy the matched text to the clipboard.
+    Copy,
+    /// Paste the matched text into the terminal.
+    Paste,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hint {
+    /// Regex labelled on screen. Hint mode is not entered when it does not
+    /// compile or nothing visible matches.
+    pub regex: String,
+    pub action: HintAction,
+}
+
+impl Hint {
+    pub fn new(regex: impl Into<String>, action: HintAction) -> Self {
+        Self {
+            regex: regex.into(),
+            action,
+        }
+    }
+
+    /// Matches URLs, file paths, git commit hashes and IPv4 addresses.
+    pub fn default_regex() -> String {
+        [URL_REGEX, PATH_REGEX, GIT_HASH_REGEX, IPV4_REGEX].join("|")
+    }
+}
+
+/// The part of a hint label still to be typed, shown at the start of
+/// its match.
+#[derive(Debug, Clone)]
+pub struct HintLabel {
+    pub point: Point,
+    pub label: String,
+}
+
+/// Generates labels of equal length so none is a prefix of another.
+pub(crate) fn generate_labels(count: usize) -> Vec<String> {
+    let alphabet: Vec<char> = HINT_ALPHABET.chars().collect();
+    let mut length = 1;
+    while alphabet.len().pow(length) < count {
+        length += 1;
+    }
+
+    (0..count)
+        .map(|mut index| {
+            let mut label = vec![alphabet[0]; length as usize];
+            for c in label.iter_mut().rev() {
+                *c = alphabet[index % alphabet.len()];
+                index /= alphabet.len();
+            }
+            label.into_iter().collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_labels, Hint};
+    use alacritty_terminal::term::search::RegexSearch;
+
+    #[test]
+    fn default_regex_compiles() {
+        assert!(RegexSearch::new(&Hint::default_regex()).is_ok());
+    }
+
+    #[test]
+    fn generate_single_char_labels() {
+        assert_eq!(generate_labels(3), vec!["j", "f", "k"]);
+    }
+
+    #[test]
+    fn generate_labels_without_shared_prefixes() {
+        let labels = generate_labels(20);
+        assert_eq!(labels[0], "jj");
+        assert_eq!(labels[19], "fk");
+        assert!(labels.iter().all(|label| label.len() == 2));
+    }
+}
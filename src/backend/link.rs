@@ -0,0 +1,64 @@
+pub(crate) const URL_REGEX: &str = r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#;
+
+/// Action id of URLs, OSC 8 hyperlinks and links opened through hints.
+pub const OPEN_LINK_ACTION: &str = "open";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkHoverStyle {
+    #[default]
+    Underline,
+    /// Swap the foreground and background colors.
+    Inverse,
+}
+
+/// Makes text matching `regex` clickable with the link-open binding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRule {
+    pub regex: String,
+    pub hover_style: LinkHoverStyle,
+    /// Passed to the link handler along with the matched text.
+    pub action: String,
+}
+
+impl LinkRule {
+    pub fn new(regex: impl Into<String>, action: impl Into<String>) -> Self {
+        Self {
+            regex: regex.into(),
+            hover_style: LinkHoverStyle::default(),
+            action: action.into(),
+        }
+    }
+
+    pub fn url() -> Self {
+        Self::new(URL_REGEX, OPEN_LINK_ACTION)
+    }
+}
+
+/// Decides what happens with a link opened from the terminal, either with
+/// the link-open binding or through a hint.
+pub trait LinkHandler: Send {
+    /// `action` is the action id of the link rule that matched `link`.
+    fn open(&mut self, link: &str, action: &str) -> anyhow::Result<()>;
+}
+
+impl<F> LinkHandler for F
+where
+    F: FnMut(&str, &str) -> anyhow::Result<()> + Send,
+{
+    fn open(&mut self, link: &str, action: &str) -> anyhow::Result<()> {
+        self(link, action)
+    }
+}
+
+/// Opens links with the default application of the system, whatever
+/// their action.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemLinkHandler;
+
+impl LinkHandler for SystemLinkHandler {
+    fn open(&mut self, link: &str, _action: &str) -> anyhow::Result<()> {
+        open::that(link).map_err(|err| {
+            anyhow::format_err!("failed to open {}: {}", link, err)
+        })
+    }
+}
@@ -1,43 +1,110 @@
+pub mod hint;
+pub mod link;
+#[cfg(unix)]
+mod osc7;
+pub mod process;
 pub mod settings;
 
+use crate::cursor::CursorStyle;
+use crate::theme::TerminalTheme;
 use crate::types::Size;
 use alacritty_terminal::event::{
     Event, EventListener, Notify, OnResize, WindowSize,
 };
 use alacritty_terminal::event_loop::{EventLoop, Msg, Notifier};
-use alacritty_terminal::grid::{Dimensions, Scroll};
-use alacritty_terminal::index::{Column, Direction, Line, Point, Side};
+use alacritty_terminal::grid::{Dimensions, Indexed, Row, Scroll};
+use alacritty_terminal::index::{
+    Boundary, Column, Direction, Line, Point, Side,
+};
 use alacritty_terminal::selection::{
     Selection, SelectionRange, SelectionType as AlacrittySelectionType,
 };
 use alacritty_terminal::sync::FairMutex;
 use alacritty_terminal::term::search::{Match, RegexIter, RegexSearch};
 use alacritty_terminal::term::{
-    self, cell::Cell, test::TermSize, viewport_to_point, Term, TermMode,
+    self,
+    cell::{Cell, Hyperlink},
+    test::TermSize,
+    viewport_to_point, ClipboardType, Term, TermDamage, TermMode,
 };
-use alacritty_terminal::{tty, Grid};
+use alacritty_terminal::tty;
+use alacritty_terminal::vi_mode::ViMotion as AlacrittyViMotion;
+use alacritty_terminal::vte::ansi::{Processor, Rgb};
 use egui::Modifiers;
-use settings::BackendSettings;
+use hint::{generate_labels, Hint, HintAction, HintLabel};
+use link::{
+    LinkHandler, LinkHoverStyle, LinkRule, SystemLinkHandler, OPEN_LINK_ACTION,
+};
+use process::ChildStatus;
+#[cfg(unix)]
+use process::{ProcessInfo, Signal};
+use settings::{BackendSettings, ClipboardReadPolicy, TerminalConfig};
 use std::borrow::Cow;
 use std::cmp::min;
-use std::io::Result;
-use std::ops::{Index, RangeInclusive};
+#[cfg(unix)]
+use std::fs::File;
+use std::io::{ErrorKind, Result};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
-use std::sync::{mpsc, Arc};
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
 pub type TerminalMode = TermMode;
 pub type PtyEvent = Event;
 pub type SelectionType = AlacrittySelectionType;
+pub type ViMotion = AlacrittyViMotion;
 
 #[derive(Debug, Clone)]
 pub enum BackendCommand {
     Write(Vec<u8>),
+    Paste(String),
     Scroll(i32),
     Resize(Size, Size),
     SelectStart(SelectionType, f32, f32),
     SelectUpdate(f32, f32),
     ProcessLink(LinkAction, Point),
     MouseReport(MouseButton, Modifiers, Point, bool),
+    SetDefaultCursorStyle(CursorStyle),
+    FocusChange(bool),
+    ToggleViMode,
+    ViMotion(ViMotion),
+    ViAction(ViAction),
+    StartHint(Hint),
+    HintInput(String),
+    StopHint,
+    ViSearchInput(String),
+    ViSearchBackspace,
+    ViSearchConfirm,
+    ViSearchCancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViAction {
+    ToggleNormalSelection,
+    ToggleLineSelection,
+    ToggleBlockSelection,
+    ToggleSemanticSelection,
+    ClearSelection,
+    /// Jump to the first line of the scrollback. Fires on the second
+    /// consecutive press of its binding, like `gg` in vim.
+    ScrollToTop,
+    ScrollToBottom,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollHalfPageUp,
+    ScrollHalfPageDown,
+    ScrollLineUp,
+    ScrollLineDown,
+    SearchNext,
+    SearchPrevious,
+    /// Start typing a pattern searched below the vi cursor, like `/` in
+    /// vim. Patterns are regular expressions, case sensitive only when
+    /// they contain an uppercase letter.
+    SearchForward,
+    /// Like `SearchForward`, searching above the cursor as `?` does.
+    SearchBackward,
 }
 
 #[derive(Debug, Clone)]
@@ -58,7 +125,7 @@ impl From<TermMode> for MouseMode {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum MouseButton {
     LeftButton = 0,
     MiddleButton = 1,
@@ -79,6 +146,29 @@ pub enum LinkAction {
     Open,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    /// Treat the pattern as a regular expression instead of literal text.
+    pub regex: bool,
+}
+
+struct SearchState {
+    regex: RegexSearch,
+    focused_match: Option<Match>,
+}
+
+struct ViSearchInput {
+    pattern: String,
+    direction: Direction,
+}
+
+struct HintState {
+    action: HintAction,
+    labelled_matches: Vec<(String, Match)>,
+    typed: String,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct TerminalSize {
     pub cell_width: u16,
@@ -133,12 +223,124 @@ impl From<TerminalSize> for WindowSize {
     }
 }
 
+/// Formats the reply to an OSC 52 clipboard read.
+type ClipboardFormatter = Arc<dyn Fn(&str) -> String + Sync + Send>;
+
+/// State the PTY event subscription thread needs to answer terminal
+/// queries on its own.
+struct PtyReplyContext {
+    theme: TerminalTheme,
+    window_size: WindowSize,
+    /// Replies to OSC 52 clipboard reads waiting for the view to fetch the
+    /// clipboard contents.
+    pending_clipboard_loads: Vec<ClipboardFormatter>,
+    /// Text stored by OSC 52 in the primary selection, which egui cannot
+    /// write to.
+    selection: String,
+    clipboard_read_policy: ClipboardReadPolicy,
+    /// Sender of the current pty event loop, replaced on respawn.
+    notifier: Notifier,
+}
+
+/// A shell running in a pty, whose output is fed into the terminal by an
+/// event loop thread until it exits.
+struct PtySession {
+    notifier: Notifier,
+    child_pid: Option<u32>,
+    /// Duplicate of the pty master, to look up its foreground process.
+    #[cfg(unix)]
+    pty_file: File,
+}
+
+impl PtySession {
+    fn spawn(
+        id: u64,
+        pty_config: &tty::Options,
+        window_size: WindowSize,
+        term: Arc<FairMutex<Term<EventProxy>>>,
+        event_proxy: EventProxy,
+        reported_cwd: Arc<Mutex<Option<PathBuf>>>,
+    ) -> Result<Self> {
+        let pty = tty::new(pty_config, window_size, id)?;
+        #[cfg(unix)]
+        let (child_pid, pty_file) =
+            (Some(pty.child().id()), pty.file().try_clone()?);
+        #[cfg(unix)]
+        let pty = osc7::Osc7Pty::new(pty, reported_cwd)?;
+        #[cfg(windows)]
+        let child_pid = pty.child_watcher().pid().map(|pid| pid.get());
+        #[cfg(windows)]
+        let _ = reported_cwd;
+        let pty_event_loop =
+            EventLoop::new(term, event_proxy, pty, false, false)?;
+        let notifier = Notifier(pty_event_loop.channel());
+        let _pty_event_loop_thread = pty_event_loop.spawn();
+
+        Ok(Self {
+            notifier,
+            child_pid,
+            #[cfg(unix)]
+            pty_file,
+        })
+    }
+}
+
+/// Spaces repaints at least `interval` apart under heavy output.
+struct RepaintThrottle {
+    interval: Duration,
+    next_repaint: Instant,
+    is_pending: bool,
+}
+
+impl RepaintThrottle {
+    fn new(max_frame_rate: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / max_frame_rate.max(1),
+            next_repaint: Instant::now(),
+            is_pending: false,
+        }
+    }
+
+    /// Returns how long to wait before requesting the next repaint.
+    fn repaint_delay(&mut self, now: Instant) -> Duration {
+        // A delayed repaint that has already happened took its slot.
+        if self.is_pending && now >= self.next_repaint {
+            self.next_repaint += self.interval;
+            self.is_pending = false;
+        }
+
+        if now >= self.next_repaint {
+            self.next_repaint = now + self.interval;
+            Duration::ZERO
+        } else {
+            self.is_pending = true;
+            self.next_repaint - now
+        }
+    }
+}
+
 pub struct TerminalBackend {
     pub id: u64,
-    pub url_regex: RegexSearch,
     term: Arc<FairMutex<Term<EventProxy>>>,
+    config: term::Config,
     size: TerminalSize,
-    notifier: Notifier,
+    reply_context: Arc<Mutex<PtyReplyContext>>,
+    pty_config: tty::Options,
+    event_proxy: EventProxy,
+    session: PtySession,
+    child_status: Arc<Mutex<ChildStatus>>,
+    reported_cwd: Arc<Mutex<Option<PathBuf>>>,
+    is_dirty: Arc<AtomicBool>,
+    /// Bumped every time the content is synced with changes.
+    content_version: u64,
+    search: Option<SearchState>,
+    vi_search: Option<ViSearchInput>,
+    hint: Option<HintState>,
+    app_context: egui::Context,
+    link_rules: Vec<(LinkRule, RegexSearch)>,
+    hovered_link_action: Option<String>,
+    link_handler: Box<dyn LinkHandler>,
+    link_error: Option<anyhow::Error>,
     last_content: RenderableContent,
 }
 
@@ -149,57 +351,146 @@ impl TerminalBackend {
         pty_event_proxy_sender: Sender<(u64, PtyEvent)>,
         settings: BackendSettings,
     ) -> Result<Self> {
-        let pty_config = tty::Options {
-            shell: Some(tty::Shell::new(settings.shell, settings.args)),
-            ..tty::Options::default()
-        };
-        let config = term::Config::default();
+        let pty_config = settings.pty_options();
+        // Invalid rules fail before a shell is spawned for nothing.
+        let link_rules = settings
+            .link_rules
+            .into_iter()
+            .map(|rule| {
+                let regex = RegexSearch::new(&rule.regex).map_err(|err| {
+                    std::io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("invalid link rule {}: {}", rule.regex, err),
+                    )
+                })?;
+                Ok((rule, regex))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut config = term::Config::default();
+        settings.terminal_config.apply_to(&mut config);
         let terminal_size = TerminalSize::default();
-        let pty = tty::new(&pty_config, terminal_size.into(), id)?;
         let (event_sender, event_receiver) = mpsc::channel();
         let event_proxy = EventProxy(event_sender);
-        let mut term = Term::new(config, &terminal_size, event_proxy.clone());
+        let term =
+            Term::new(config.clone(), &terminal_size, event_proxy.clone());
+        // The terminal starts fully damaged, so the first sync fills it in.
         let initial_content = RenderableContent {
-            grid: term.grid().clone(),
-            selectable_range: None,
             terminal_mode: *term.mode(),
             terminal_size,
-            cursor: term.grid_mut().cursor_cell().clone(),
-            hovered_hyperlink: None,
+            cursor_style: term.cursor_style(),
+            ..RenderableContent::default()
         };
         let term = Arc::new(FairMutex::new(term));
-        let pty_event_loop =
-            EventLoop::new(term.clone(), event_proxy, pty, false, false)?;
-        let notifier = Notifier(pty_event_loop.channel());
-        let url_regex = RegexSearch::new(r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#).unwrap();
-        let _pty_event_loop_thread = pty_event_loop.spawn();
+        let reported_cwd = Arc::new(Mutex::new(None));
+        let session = PtySession::spawn(
+            id,
+            &pty_config,
+            terminal_size.into(),
+            term.clone(),
+            event_proxy.clone(),
+            reported_cwd.clone(),
+        )?;
+        let reply_context = Arc::new(Mutex::new(PtyReplyContext {
+            theme: TerminalTheme::default(),
+            window_size: terminal_size.into(),
+            pending_clipboard_loads: Vec::new(),
+            selection: String::new(),
+            clipboard_read_policy: settings.clipboard_read_policy,
+            notifier: Notifier(session.notifier.0.clone()),
+        }));
+        let subscription_term = Arc::downgrade(&term);
+        let subscription_reply_context = reply_context.clone();
+        let child_status = Arc::new(Mutex::new(ChildStatus::Running));
+        let subscription_child_status = child_status.clone();
+        let is_dirty = Arc::new(AtomicBool::new(true));
+        let subscription_is_dirty = is_dirty.clone();
+        let mut repaint_throttle =
+            RepaintThrottle::new(settings.max_frame_rate);
+        let subscription_app_context = app_context.clone();
+        // Runs until the backend and the pty event loop are gone, since the
+        // terminal outlives the shells respawned in it.
         let _pty_event_subscription = std::thread::Builder::new()
             .name(format!("pty_event_subscription_{}", id))
-            .spawn(move || loop {
-                if let Ok(event) = event_receiver.recv() {
+            .spawn(move || {
+                while let Ok(event) = event_receiver.recv() {
+                    match event {
+                        Event::Wakeup => {
+                            subscription_is_dirty
+                                .store(true, Ordering::Release);
+                        },
+                        // Sent before `Exit`, but only if the child exited
+                        // with a code.
+                        Event::ChildExit(code) => {
+                            *subscription_child_status.lock().unwrap() =
+                                ChildStatus::Exited(code);
+                        },
+                        Event::Exit => {
+                            let mut child_status =
+                                subscription_child_status.lock().unwrap();
+                            if *child_status == ChildStatus::Running {
+                                *child_status = ChildStatus::Terminated;
+                            }
+                        },
+                        _ => {},
+                    }
+
+                    let Some(event) = reply_to_event(
+                        event,
+                        &subscription_term,
+                        &subscription_reply_context,
+                        &subscription_app_context,
+                    ) else {
+                        continue;
+                    };
+
                     pty_event_proxy_sender
                         .send((id, event.clone()))
                         .unwrap_or_else(|_| {
                             panic!("pty_event_subscription_{}: sending PtyEvent is failed", id)
                         });
-                    app_context.clone().request_repaint();
-                    if let Event::Exit = event {
-                        break;
-                    }
+                    subscription_app_context.request_repaint_after(
+                        repaint_throttle.repaint_delay(Instant::now()),
+                    );
                 }
             })?;
 
         Ok(Self {
             id,
-            url_regex,
             term: term.clone(),
+            config,
             size: terminal_size,
-            notifier,
+            reply_context,
+            pty_config,
+            event_proxy,
+            session,
+            child_status,
+            reported_cwd,
+            is_dirty,
+            content_version: 0,
+            search: None,
+            vi_search: None,
+            hint: None,
+            app_context,
+            link_rules,
+            hovered_link_action: None,
+            link_handler: Box::new(SystemLinkHandler),
+            link_error: None,
             last_content: initial_content,
         })
     }
 
     pub fn process_command(&mut self, cmd: BackendCommand) {
+        // These are sent every frame and mark the content dirty themselves
+        // only when something changes.
+        if !matches!(
+            cmd,
+            BackendCommand::Resize(..)
+                | BackendCommand::SetDefaultCursorStyle(_)
+                | BackendCommand::FocusChange(_)
+        ) {
+            self.mark_dirty();
+        }
+
         let term = self.term.clone();
         let mut term = term.lock();
         match cmd {
@@ -207,6 +498,12 @@ impl TerminalBackend {
                 self.write(input);
                 term.scroll_display(Scroll::Bottom);
             },
+            BackendCommand::Paste(text) => {
+                let is_bracketed =
+                    term.mode().contains(TermMode::BRACKETED_PASTE);
+                self.write(format_paste(&text, is_bracketed));
+                term.scroll_display(Scroll::Bottom);
+            },
             BackendCommand::Scroll(delta) => {
                 self.scroll(&mut term, delta);
             },
@@ -225,6 +522,50 @@ impl TerminalBackend {
             BackendCommand::MouseReport(button, modifiers, point, pressed) => {
                 self.process_mouse_report(button, modifiers, point, pressed);
             },
+            BackendCommand::SetDefaultCursorStyle(style) => {
+                self.set_default_cursor_style(&mut term, style);
+            },
+            BackendCommand::FocusChange(is_focused) => {
+                self.report_focus_change(&term, is_focused);
+            },
+            BackendCommand::ToggleViMode => {
+                if term.mode().contains(TermMode::VI) {
+                    term.selection = None;
+                    self.vi_search = None;
+                }
+                term.toggle_vi_mode();
+            },
+            BackendCommand::ViMotion(motion) => {
+                term.vi_motion(motion);
+            },
+            BackendCommand::ViAction(action) => {
+                self.process_vi_action(&mut term, action);
+            },
+            BackendCommand::StartHint(hint) => {
+                self.start_hint(&term, hint);
+            },
+            BackendCommand::HintInput(text) => {
+                self.process_hint_input(&mut term, &text);
+            },
+            BackendCommand::StopHint => {
+                self.hint = None;
+            },
+            BackendCommand::ViSearchInput(text) => {
+                if let Some(input) = &mut self.vi_search {
+                    input.pattern.push_str(&text);
+                }
+            },
+            BackendCommand::ViSearchBackspace => {
+                if let Some(input) = &mut self.vi_search {
+                    input.pattern.pop();
+                }
+            },
+            BackendCommand::ViSearchConfirm => {
+                self.confirm_vi_search(&mut term);
+            },
+            BackendCommand::ViSearchCancel => {
+                self.vi_search = None;
+            },
         };
     }
 
@@ -243,20 +584,22 @@ impl TerminalBackend {
         viewport_to_point(display_offset, Point::new(line, col))
     }
 
+    /// Returns the selected text the way alacritty copies it: soft-wrapped
+    /// lines are joined, trailing whitespace is trimmed and the selection
+    /// may reach into scrollback outside of the viewport.
     pub fn selectable_content(&self) -> String {
-        let content = self.last_content();
-        let mut result = String::new();
-        if let Some(range) = content.selectable_range {
-            for indexed in content.grid.display_iter() {
-                if range.contains(indexed.point) {
-                    result.push(indexed.c);
-                }
-            }
-        }
-        result
+        self.term.lock().selection_to_string().unwrap_or_default()
     }
 
+    /// Updates the content with the viewport changes since the last sync.
+    /// Only the lines alacritty reports as damaged are copied, and nothing
+    /// is done at all unless the terminal is dirty.
     pub fn sync(&mut self) -> &RenderableContent {
+        if !self.is_dirty.swap(false, Ordering::AcqRel) {
+            return self.last_content();
+        }
+
+        self.content_version += 1;
         let term = self.term.clone();
         let mut terminal = term.lock();
         let selectable_range = match &terminal.selection {
@@ -264,12 +607,51 @@ impl TerminalBackend {
             None => None,
         };
 
+        self.sync_damaged_rows(&mut terminal);
         let cursor = terminal.grid_mut().cursor_cell().clone();
-        self.last_content.grid = terminal.grid().clone();
+        self.last_content.cursor_point = terminal.grid().cursor.point;
         self.last_content.selectable_range = selectable_range;
         self.last_content.cursor = cursor.clone();
+        self.last_content.cursor_style = terminal.cursor_style();
+        self.last_content.vi_mode_cursor = terminal.vi_mode_cursor.point;
         self.last_content.terminal_mode = *terminal.mode();
         self.last_content.terminal_size = self.size;
+        match &mut self.search {
+            Some(search) => {
+                self.last_content.search_matches =
+                    visible_regex_match_iter(&terminal, &mut search.regex)
+                        .collect();
+                self.last_content.focused_search_match =
+                    search.focused_match.clone();
+            },
+            None => {
+                self.last_content.search_matches.clear();
+                self.last_content.focused_search_match = None;
+            },
+        }
+        self.last_content.vi_search_prompt =
+            self.vi_search.as_ref().map(|input| {
+                let prefix = match input.direction {
+                    Direction::Right => '/',
+                    Direction::Left => '?',
+                };
+                format!("{}{}", prefix, input.pattern)
+            });
+        self.last_content.hint_labels = match &self.hint {
+            Some(hint) => hint
+                .labelled_matches
+                .iter()
+                .filter_map(|(label, rm)| {
+                    label.strip_prefix(hint.typed.as_str()).map(|rest| {
+                        HintLabel {
+                            point: *rm.start(),
+                            label: rest.to_string(),
+                        }
+                    })
+                })
+                .collect(),
+            None => vec![],
+        };
         self.last_content()
     }
 
@@ -277,6 +659,388 @@ impl TerminalBackend {
         &self.last_content
     }
 
+    pub(crate) fn content_version(&self) -> u64 {
+        self.content_version
+    }
+
+    pub fn child_pid(&self) -> Option<u32> {
+        self.session.child_pid
+    }
+
+    /// Updated once the pty event loop notices the child exited, right
+    /// before `PtyEvent::Exit` is sent.
+    pub fn child_status(&self) -> ChildStatus {
+        *self.child_status.lock().unwrap()
+    }
+
+    pub fn is_child_alive(&self) -> bool {
+        self.child_status() == ChildStatus::Running
+    }
+
+    /// Starts the configured shell again in this terminal once the previous
+    /// one has exited. With `keep_scrollback` its output stays above a
+    /// separator line, otherwise the terminal is reset.
+    pub fn respawn(&mut self, keep_scrollback: bool) -> Result<()> {
+        if self.is_child_alive() {
+            return Err(std::io::Error::other(
+                "child process is still running",
+            ));
+        }
+
+        {
+            let mut terminal = self.term.lock();
+            if keep_scrollback {
+                write_session_separator(&mut terminal);
+            } else {
+                *terminal = Term::new(
+                    self.config.clone(),
+                    &self.size,
+                    self.event_proxy.clone(),
+                );
+            }
+        }
+
+        *self.reported_cwd.lock().unwrap() = None;
+        // Marked running before spawning, a child that exits right away
+        // could otherwise have its exit status overwritten.
+        let previous_status = std::mem::replace(
+            &mut *self.child_status.lock().unwrap(),
+            ChildStatus::Running,
+        );
+        self.session = match PtySession::spawn(
+            self.id,
+            &self.pty_config,
+            self.size.into(),
+            self.term.clone(),
+            self.event_proxy.clone(),
+            self.reported_cwd.clone(),
+        ) {
+            Ok(session) => session,
+            Err(err) => {
+                *self.child_status.lock().unwrap() = previous_status;
+                return Err(err);
+            },
+        };
+        self.reply_context.lock().unwrap().notifier =
+            Notifier(self.session.notifier.0.clone());
+        self.search = None;
+        self.vi_search = None;
+        self.hint = None;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    pub fn send_signal(&self, signal: Signal) -> Result<()> {
+        process::kill(self.live_child_pid()?, signal)
+    }
+
+    /// Signals the process group given by `foreground_process_group`.
+    #[cfg(unix)]
+    pub fn send_signal_to_foreground(&self, signal: Signal) -> Result<()> {
+        self.live_child_pid()?;
+        let pgid = process::foreground_process_group(&self.session.pty_file)?;
+        process::kill_process_group(pgid, signal)
+    }
+
+    /// Returns the process group in the foreground of the terminal, which
+    /// is the job the shell is running, or the shell itself when idle.
+    #[cfg(unix)]
+    pub fn foreground_process_group(&self) -> Option<u32> {
+        self.live_child_pid().ok()?;
+        process::foreground_process_group(&self.session.pty_file).ok()
+    }
+
+    /// Whether the shell is running a job, to confirm before closing it.
+    #[cfg(unix)]
+    pub fn has_foreground_job(&self) -> bool {
+        self.foreground_process_group()
+            .is_some_and(|pgid| Some(pgid) != self.session.child_pid)
+    }
+
+    /// Describes the leader of the foreground process group. Only
+    /// available on Linux.
+    #[cfg(unix)]
+    pub fn foreground_process(&self) -> Option<ProcessInfo> {
+        process::process_info(self.foreground_process_group()?)
+    }
+
+    /// Returns the directory last reported by the shell with OSC 7, or the
+    /// one of the foreground process if the shell reports none.
+    pub fn current_dir(&self) -> Option<PathBuf> {
+        let reported_cwd = self.reported_cwd.lock().unwrap().clone();
+        #[cfg(unix)]
+        let reported_cwd = reported_cwd
+            .or_else(|| self.foreground_process().and_then(|info| info.cwd));
+        reported_cwd
+    }
+
+    /// The pid may have been reused once the child is gone.
+    #[cfg(unix)]
+    fn live_child_pid(&self) -> Result<u32> {
+        match self.session.child_pid {
+            Some(pid) if self.is_child_alive() => Ok(pid),
+            _ => Err(std::io::Error::new(
+                ErrorKind::NotFound,
+                "child process has exited",
+            )),
+        }
+    }
+
+    /// Whether the terminal changed since the last sync, either from new
+    /// output or from a command.
+    pub fn is_dirty(&self) -> bool {
+        self.is_dirty.load(Ordering::Acquire)
+    }
+
+    fn mark_dirty(&self) {
+        self.is_dirty.store(true, Ordering::Release);
+    }
+
+    fn sync_damaged_rows(&mut self, terminal: &mut Term<EventProxy>) {
+        let screen_lines = terminal.screen_lines();
+        let columns = terminal.columns();
+        let display_offset = terminal.grid().display_offset();
+        let rows = &mut self.last_content.rows;
+        // Rows synced for another size or scroll position can't be patched.
+        let is_stale = rows.len() != screen_lines
+            || rows.first().is_some_and(|row| row.len() != columns)
+            || self.last_content.display_offset != display_offset;
+        let damaged_lines = match terminal.damage() {
+            TermDamage::Full => None,
+            TermDamage::Partial(_) if is_stale => None,
+            TermDamage::Partial(lines) => Some(lines.collect::<Vec<_>>()),
+        };
+        terminal.reset_damage();
+
+        self.last_content.display_offset = display_offset;
+        let grid_line = |line: usize| Line(line as i32 - display_offset as i32);
+        match damaged_lines {
+            None => {
+                *rows = (0..screen_lines)
+                    .map(|line| terminal.grid()[grid_line(line)].clone())
+                    .collect();
+            },
+            Some(damaged_lines) => {
+                for bounds in damaged_lines {
+                    let row = &terminal.grid()[grid_line(bounds.line)];
+                    for column in bounds.left..=bounds.right.min(columns - 1) {
+                        rows[bounds.line][Column(column)] =
+                            row[Column(column)].clone();
+                    }
+                }
+            },
+        }
+    }
+
+    /// Answers an OSC 52 clipboard read forwarded as
+    /// `PtyEvent::ClipboardLoad`, formatting `text` with the function that
+    /// came with the event. Unlike a paste, it does not scroll the viewport.
+    pub fn answer_clipboard_load(
+        &self,
+        text: &str,
+        format: &(dyn Fn(&str) -> String + Sync + Send),
+    ) {
+        self.write(format(text).into_bytes());
+    }
+
+    pub(crate) fn has_pending_clipboard_loads(&self) -> bool {
+        !self
+            .reply_context
+            .lock()
+            .unwrap()
+            .pending_clipboard_loads
+            .is_empty()
+    }
+
+    /// Answers the clipboard reads allowed by
+    /// `ClipboardReadPolicy::Allow` with the clipboard contents.
+    pub(crate) fn answer_pending_clipboard_loads(&self, text: &str) {
+        let pending_clipboard_loads = std::mem::take(
+            &mut self.reply_context.lock().unwrap().pending_clipboard_loads,
+        );
+        for format in pending_clipboard_loads {
+            self.answer_clipboard_load(text, &*format);
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: &TerminalTheme) {
+        let mut reply_context = self.reply_context.lock().unwrap();
+        if reply_context.theme != *theme {
+            reply_context.theme = theme.clone();
+            self.mark_dirty();
+        }
+    }
+
+    pub fn set_terminal_config(&mut self, terminal_config: &TerminalConfig) {
+        terminal_config.apply_to(&mut self.config);
+        self.term.lock().set_options(self.config.clone());
+        self.mark_dirty();
+    }
+
+    /// Starts searching the scrollback for `pattern` and scrolls to the
+    /// match closest to the bottom of the viewport. Returns whether a match
+    /// was found.
+    pub fn start_search(
+        &mut self,
+        pattern: &str,
+        options: SearchOptions,
+    ) -> anyhow::Result<bool> {
+        if pattern.is_empty() {
+            self.clear_search();
+            return Ok(false);
+        }
+
+        let regex = build_search_regex(pattern, options)?;
+        self.search = Some(SearchState {
+            regex,
+            focused_match: None,
+        });
+        self.mark_dirty();
+
+        let term = self.term.clone();
+        let mut term = term.lock();
+        let viewport_end = Point::new(
+            Line(
+                term.bottommost_line().0 - term.grid().display_offset() as i32,
+            ),
+            term.last_column(),
+        );
+        Ok(self.focus_search_match(
+            &mut term,
+            viewport_end,
+            Direction::Left,
+            Side::Right,
+        ))
+    }
+
+    /// Moves to the next match below the focused one, wrapping around at
+    /// the end of the scrollback.
+    pub fn search_next(&mut self) -> bool {
+        self.mark_dirty();
+        let term = self.term.clone();
+        let mut term = term.lock();
+        self.focus_next_search_match(&mut term)
+    }
+
+    /// Moves to the previous match above the focused one, wrapping around
+    /// at the start of the scrollback.
+    pub fn search_previous(&mut self) -> bool {
+        self.mark_dirty();
+        let term = self.term.clone();
+        let mut term = term.lock();
+        self.focus_previous_search_match(&mut term)
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search = None;
+        self.mark_dirty();
+    }
+
+    /// Searches the pattern typed in vi mode from the vi cursor. Invalid
+    /// patterns clear the search.
+    fn confirm_vi_search(&mut self, terminal: &mut Term<EventProxy>) {
+        let Some(input) = self.vi_search.take() else {
+            return;
+        };
+        if input.pattern.is_empty() {
+            return;
+        }
+
+        let options = SearchOptions {
+            case_sensitive: input.pattern.chars().any(char::is_uppercase),
+            regex: true,
+        };
+        self.search =
+            build_search_regex(&input.pattern, options)
+                .ok()
+                .map(|regex| SearchState {
+                    regex,
+                    focused_match: None,
+                });
+
+        let cursor = terminal.vi_mode_cursor.point;
+        match input.direction {
+            Direction::Right => self.focus_search_match(
+                terminal,
+                cursor.add(&*terminal, Boundary::None, 1),
+                Direction::Right,
+                Side::Left,
+            ),
+            Direction::Left => self.focus_search_match(
+                terminal,
+                cursor.sub(&*terminal, Boundary::None, 1),
+                Direction::Left,
+                Side::Right,
+            ),
+        };
+    }
+
+    fn focus_next_search_match(&mut self, term: &mut Term<EventProxy>) -> bool {
+        let origin = match self.focused_search_match() {
+            Some(focused) => focused.end().add(&*term, Boundary::None, 1),
+            None => Point::new(
+                Line(-(term.grid().display_offset() as i32)),
+                Column(0),
+            ),
+        };
+        self.focus_search_match(term, origin, Direction::Right, Side::Left)
+    }
+
+    fn focus_previous_search_match(
+        &mut self,
+        term: &mut Term<EventProxy>,
+    ) -> bool {
+        let origin = match self.focused_search_match() {
+            Some(focused) => focused.start().sub(&*term, Boundary::None, 1),
+            None => Point::new(
+                Line(
+                    term.bottommost_line().0
+                        - term.grid().display_offset() as i32,
+                ),
+                term.last_column(),
+            ),
+        };
+        self.focus_search_match(term, origin, Direction::Left, Side::Right)
+    }
+
+    fn focused_search_match(&self) -> Option<Match> {
+        self.search
+            .as_ref()
+            .and_then(|search| search.focused_match.clone())
+    }
+
+    fn focus_search_match(
+        &mut self,
+        terminal: &mut Term<EventProxy>,
+        origin: Point,
+        direction: Direction,
+        side: Side,
+    ) -> bool {
+        let Some(search) = &mut self.search else {
+            return false;
+        };
+
+        search.focused_match = terminal.search_next(
+            &mut search.regex,
+            origin,
+            direction,
+            side,
+            None,
+        );
+        match &search.focused_match {
+            Some(focused) if terminal.mode().contains(TermMode::VI) => {
+                terminal.vi_goto_point(*focused.start());
+                true
+            },
+            Some(focused) => {
+                terminal.scroll_to_point(*focused.start());
+                true
+            },
+            None => false,
+        }
+    }
+
     fn process_link_action(
         &mut self,
         terminal: &Term<EventProxy>,
@@ -285,37 +1049,153 @@ impl TerminalBackend {
     ) {
         match link_action {
             LinkAction::Hover => {
-                self.last_content.hovered_hyperlink = self.regex_match_at(
-                    terminal,
-                    point,
-                    &mut self.url_regex.clone(),
+                // The point was computed for the size the view last saw,
+                // which can be past the grid after it shrinks.
+                let point = Point::new(
+                    point.line.clamp(
+                        terminal.topmost_line(),
+                        terminal.bottommost_line(),
+                    ),
+                    point.column.min(terminal.last_column()),
                 );
+                // Links set with OSC 8 take precedence over link rules.
+                let osc8_hyperlink = terminal.grid()[point].hyperlink();
+                let hovered_link = match osc8_hyperlink {
+                    Some(_) => None,
+                    None => {
+                        self.link_rules.iter_mut().find_map(|(rule, regex)| {
+                            Self::regex_match_at(terminal, point, regex)
+                                .map(|rm| (rule, rm))
+                        })
+                    },
+                };
+
+                self.last_content.hovered_link_style = hovered_link
+                    .as_ref()
+                    .map_or(LinkHoverStyle::default(), |(rule, _)| {
+                        rule.hover_style
+                    });
+                self.hovered_link_action =
+                    match (&osc8_hyperlink, &hovered_link) {
+                        (Some(_), _) => Some(OPEN_LINK_ACTION.to_string()),
+                        (None, Some((rule, _))) => Some(rule.action.clone()),
+                        (None, None) => None,
+                    };
+                self.last_content.hovered_hyperlink =
+                    hovered_link.map(|(_, rm)| rm);
+                self.last_content.hovered_osc8_hyperlink = osc8_hyperlink;
             },
             LinkAction::Clear => {
+                self.hovered_link_action = None;
                 self.last_content.hovered_hyperlink = None;
+                self.last_content.hovered_osc8_hyperlink = None;
             },
             LinkAction::Open => {
-                self.open_link();
+                self.open_link(terminal);
             },
         };
     }
 
-    fn open_link(&self) {
-        if let Some(range) = &self.last_content.hovered_hyperlink {
-            let start = range.start();
-            let end = range.end();
+    /// Replaces the handler of opened links, which opens them with the
+    /// default application of the system unless set.
+    pub fn set_link_handler(&mut self, handler: impl LinkHandler + 'static) {
+        self.link_handler = Box::new(handler);
+    }
 
-            let mut url = String::from(self.last_content.grid.index(*start).c);
-            for indexed in self.last_content.grid.iter_from(*start) {
-                url.push(indexed.c);
-                if indexed.point == *end {
-                    break;
-                }
-            }
+    /// Returns the error of the last link that failed to open, if any.
+    pub fn take_link_error(&mut self) -> Option<anyhow::Error> {
+        self.link_error.take()
+    }
+
+    fn open_url(&mut self, url: &str, action: &str) {
+        if let Err(err) = self.link_handler.open(url, action) {
+            self.link_error = Some(err);
+        }
+    }
+
+    fn open_link(&mut self, terminal: &Term<EventProxy>) {
+        let Some(action) = self.hovered_link_action.clone() else {
+            return;
+        };
+
+        if let Some(hyperlink) =
+            self.last_content.hovered_osc8_hyperlink.clone()
+        {
+            self.open_url(hyperlink.uri(), &action);
+        } else if let Some(range) = &self.last_content.hovered_hyperlink {
+            let url = terminal.bounds_to_string(*range.start(), *range.end());
+            self.open_url(&url, &action);
+        }
+    }
+
+    fn start_hint(&mut self, terminal: &Term<EventProxy>, hint: Hint) {
+        let Ok(mut regex) = RegexSearch::new(&hint.regex) else {
+            self.hint = None;
+            return;
+        };
+
+        // Labels are handed out from the bottom, closest to the prompt.
+        let viewport_start = Line(-(terminal.grid().display_offset() as i32));
+        let mut matches: Vec<Match> =
+            visible_regex_match_iter(terminal, &mut regex)
+                .filter(|rm| rm.start().line >= viewport_start)
+                .collect();
+        matches.reverse();
 
-            open::that(url).unwrap_or_else(|_| {
-                panic!("link opening is failed");
+        self.hint = if matches.is_empty() {
+            None
+        } else {
+            Some(HintState {
+                action: hint.action,
+                labelled_matches: generate_labels(matches.len())
+                    .into_iter()
+                    .zip(matches)
+                    .collect(),
+                typed: String::new(),
             })
+        };
+    }
+
+    /// Narrows the hints down to the labels starting with the typed text
+    /// and runs the hint action once a whole label is typed. Typing
+    /// something no label starts with leaves hint mode.
+    fn process_hint_input(
+        &mut self,
+        terminal: &mut Term<EventProxy>,
+        text: &str,
+    ) {
+        let Some(hint) = &mut self.hint else {
+            return;
+        };
+
+        hint.typed.push_str(&text.to_lowercase());
+        let mut candidates = hint
+            .labelled_matches
+            .iter()
+            .filter(|(label, _)| label.starts_with(&hint.typed));
+        let selected = match (candidates.next(), candidates.next()) {
+            (Some((label, rm)), None) if *label == hint.typed => {
+                Some((hint.action, rm.clone()))
+            },
+            (Some(_), _) => return,
+            (None, _) => None,
+        };
+
+        self.hint = None;
+        if let Some((action, rm)) = selected {
+            let text = terminal.bounds_to_string(*rm.start(), *rm.end());
+            match action {
+                HintAction::Open => self.open_url(&text, OPEN_LINK_ACTION),
+                HintAction::Copy => {
+                    self.app_context.copy_text(text);
+                },
+                HintAction::Paste => {
+                    let is_bracketed =
+                        terminal.mode().contains(TermMode::BRACKETED_PASTE);
+                    self.write(format_paste(&text, is_bracketed));
+                    terminal.scroll_display(Scroll::Bottom);
+                },
+            }
         }
     }
 
@@ -366,7 +1246,7 @@ impl TerminalBackend {
             c
         );
 
-        self.notifier.notify(msg.as_bytes().to_vec());
+        self.session.notifier.notify(msg.as_bytes().to_vec());
     }
 
     fn normal_mouse_report(&self, point: Point, button: u8, is_utf8: bool) {
@@ -398,7 +1278,7 @@ impl TerminalBackend {
             msg.push(32 + 1 + line.0 as u8);
         }
 
-        self.notifier.notify(msg);
+        self.session.notifier.notify(msg);
     }
 
     fn start_selection(
@@ -470,7 +1350,9 @@ impl TerminalBackend {
                 num_cols: cols,
             };
 
-            self.notifier.on_resize(self.size.into());
+            self.mark_dirty();
+            self.session.notifier.on_resize(self.size.into());
+            self.reply_context.lock().unwrap().window_size = self.size.into();
             terminal.resize(TermSize::new(
                 self.size.num_cols as usize,
                 self.size.num_lines as usize,
@@ -478,8 +1360,131 @@ impl TerminalBackend {
         }
     }
 
+    fn set_default_cursor_style(
+        &mut self,
+        terminal: &mut Term<EventProxy>,
+        style: CursorStyle,
+    ) {
+        if self.config.default_cursor_style != style {
+            self.config.default_cursor_style = style;
+            terminal.set_options(self.config.clone());
+            self.mark_dirty();
+        }
+    }
+
+    fn report_focus_change(
+        &self,
+        terminal: &Term<EventProxy>,
+        is_focused: bool,
+    ) {
+        if terminal.mode().contains(TermMode::FOCUS_IN_OUT) {
+            let report = if is_focused { "\x1b[I" } else { "\x1b[O" };
+            self.write(report.as_bytes());
+        }
+    }
+
+    fn process_vi_action(
+        &mut self,
+        terminal: &mut Term<EventProxy>,
+        action: ViAction,
+    ) {
+        if !terminal.mode().contains(TermMode::VI) {
+            return;
+        }
+
+        let screen_lines = terminal.screen_lines() as i32;
+        match action {
+            ViAction::ToggleNormalSelection => {
+                Self::toggle_vi_selection(terminal, SelectionType::Simple)
+            },
+            ViAction::ToggleLineSelection => {
+                Self::toggle_vi_selection(terminal, SelectionType::Lines)
+            },
+            ViAction::ToggleBlockSelection => {
+                Self::toggle_vi_selection(terminal, SelectionType::Block)
+            },
+            ViAction::ToggleSemanticSelection => {
+                Self::toggle_vi_selection(terminal, SelectionType::Semantic)
+            },
+            ViAction::ClearSelection => terminal.selection = None,
+            ViAction::ScrollToTop => {
+                terminal.scroll_display(Scroll::Top);
+                terminal.vi_mode_cursor.point.line = terminal.topmost_line();
+                terminal.vi_motion(ViMotion::FirstOccupied);
+            },
+            ViAction::ScrollToBottom => {
+                terminal.scroll_display(Scroll::Bottom);
+                terminal.vi_mode_cursor.point.line = terminal.bottommost_line();
+                terminal.vi_motion(ViMotion::FirstOccupied);
+            },
+            ViAction::ScrollPageUp => Self::vi_scroll(terminal, screen_lines),
+            ViAction::ScrollPageDown => {
+                Self::vi_scroll(terminal, -screen_lines)
+            },
+            ViAction::ScrollHalfPageUp => {
+                Self::vi_scroll(terminal, screen_lines / 2)
+            },
+            ViAction::ScrollHalfPageDown => {
+                Self::vi_scroll(terminal, -screen_lines / 2)
+            },
+            ViAction::ScrollLineUp => Self::vi_scroll(terminal, 1),
+            ViAction::ScrollLineDown => Self::vi_scroll(terminal, -1),
+            ViAction::SearchNext => {
+                self.focus_next_search_match(terminal);
+            },
+            ViAction::SearchPrevious => {
+                self.focus_previous_search_match(terminal);
+            },
+            ViAction::SearchForward => {
+                self.vi_search = Some(ViSearchInput {
+                    pattern: String::new(),
+                    direction: Direction::Right,
+                });
+            },
+            ViAction::SearchBackward => {
+                self.vi_search = Some(ViSearchInput {
+                    pattern: String::new(),
+                    direction: Direction::Left,
+                });
+            },
+        }
+    }
+
+    /// Based on alacritty/src/input/mod.rs > toggle_selection
+    fn toggle_vi_selection(
+        terminal: &mut Term<EventProxy>,
+        selection_type: SelectionType,
+    ) {
+        match &mut terminal.selection {
+            Some(selection)
+                if selection.ty == selection_type && !selection.is_empty() =>
+            {
+                terminal.selection = None;
+            },
+            Some(selection) if !selection.is_empty() => {
+                selection.ty = selection_type;
+            },
+            _ => {
+                let mut selection = Selection::new(
+                    selection_type,
+                    terminal.vi_mode_cursor.point,
+                    Side::Left,
+                );
+                selection.include_all();
+                terminal.selection = Some(selection);
+            },
+        }
+    }
+
+    /// Scrolls the viewport and moves the vi cursor along with it.
+    fn vi_scroll(terminal: &mut Term<EventProxy>, lines: i32) {
+        terminal.vi_mode_cursor =
+            terminal.vi_mode_cursor.scroll(terminal, lines);
+        terminal.scroll_display(Scroll::Delta(lines));
+    }
+
     fn write<I: Into<Cow<'static, [u8]>>>(&self, input: I) {
-        self.notifier.notify(input);
+        self.session.notifier.notify(input);
     }
 
     fn scroll(&mut self, terminal: &mut Term<EventProxy>, delta_value: i32) {
@@ -498,9 +1503,9 @@ impl TerminalBackend {
                     content.push(line_cmd);
                 }
 
-                self.notifier.notify(content);
+                self.session.notifier.notify(content);
             } else {
-                terminal.grid_mut().scroll_display(scroll);
+                terminal.scroll_display(scroll);
             }
         }
     }
@@ -508,7 +1513,6 @@ impl TerminalBackend {
     /// Based on alacritty/src/display/hint.rs > regex_match_at
     /// Retrieve the match, if the specified point is inside the content matching the regex.
     fn regex_match_at(
-        &self,
         terminal: &Term<EventProxy>,
         point: Point,
         regex: &mut RegexSearch,
@@ -519,6 +1523,150 @@ impl TerminalBackend {
     }
 }
 
+/// Wraps pasted text in bracketed paste markers, dropping any markers
+/// embedded in the text itself, or sends newlines as carriage returns
+/// like a typed Enter when the application did not enable bracketed paste.
+fn format_paste(text: &str, is_bracketed: bool) -> Vec<u8> {
+    if is_bracketed {
+        let text = text.replace("\x1b[200~", "").replace("\x1b[201~", "");
+        format!("\x1b[200~{}\x1b[201~", text).into_bytes()
+    } else {
+        text.replace("\r\n", "\r").replace('\n', "\r").into_bytes()
+    }
+}
+
+fn build_search_regex(
+    pattern: &str,
+    options: SearchOptions,
+) -> anyhow::Result<RegexSearch> {
+    let pattern = if options.regex {
+        Cow::Borrowed(pattern)
+    } else {
+        Cow::Owned(escape_regex(pattern))
+    };
+    let case_flag = if options.case_sensitive {
+        "(?-i)"
+    } else {
+        "(?i)"
+    };
+    RegexSearch::new(&format!("{}{}", case_flag, pattern))
+        .map_err(|err| anyhow::format_err!("invalid pattern: {}", err))
+}
+
+/// Escapes the regex meta characters of a literal search pattern.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Leaves the modes a program that exited may have left on.
+const SESSION_RESET: &str =
+    "\x1b[?1049l\x1b[r\x1b[0m\x1b[4l\x1b[0 q\x1b(B\x1b>\
+    \x1b[=0;1u\x1b[?1l\x1b[?6l\x1b[?7h\x1b[?25h\x1b[?1000l\x1b[?1002l\
+    \x1b[?1003l\x1b[?1004l\x1b[?1006l\x1b[?2004l";
+
+/// Resets the terminal modes and draws a dim line below the output of the
+/// previous session.
+fn write_session_separator(terminal: &mut Term<EventProxy>) {
+    let mut parser: Processor = Processor::new();
+    parser.advance(terminal, SESSION_RESET.as_bytes());
+    if terminal.grid().cursor.point.column.0 > 0 {
+        parser.advance(terminal, b"\r\n");
+    }
+
+    let separator = format!(
+        "\x1b[2m{}\x1b[0m\r\n",
+        "\u{2500}".repeat(terminal.columns())
+    );
+    parser.advance(terminal, separator.as_bytes());
+}
+
+/// Answers the events that expect a reply written back to the PTY and
+/// returns the ones that should be forwarded to the application.
+fn reply_to_event(
+    event: Event,
+    term: &Weak<FairMutex<Term<EventProxy>>>,
+    reply_context: &Mutex<PtyReplyContext>,
+    app_context: &egui::Context,
+) -> Option<Event> {
+    match event {
+        Event::ClipboardStore(ClipboardType::Clipboard, text) => {
+            app_context.copy_text(text);
+            None
+        },
+        Event::ClipboardStore(ClipboardType::Selection, text) => {
+            reply_context.lock().unwrap().selection = text;
+            None
+        },
+        Event::ClipboardLoad(clipboard_type, format) => {
+            let mut reply_context = reply_context.lock().unwrap();
+            match (reply_context.clipboard_read_policy, clipboard_type) {
+                (ClipboardReadPolicy::Allow, ClipboardType::Clipboard) => {
+                    // egui can only read the clipboard from the view, which
+                    // answers once the contents arrive.
+                    reply_context.pending_clipboard_loads.push(format);
+                    app_context.request_repaint();
+                    None
+                },
+                (ClipboardReadPolicy::Allow, ClipboardType::Selection) => {
+                    let text = format(&reply_context.selection);
+                    reply_context.notifier.notify(text.into_bytes());
+                    None
+                },
+                (ClipboardReadPolicy::Deny, _) => None,
+                (ClipboardReadPolicy::Ask, _) => {
+                    Some(Event::ClipboardLoad(clipboard_type, format))
+                },
+            }
+        },
+        Event::PtyWrite(text) => {
+            reply_context
+                .lock()
+                .unwrap()
+                .notifier
+                .notify(text.into_bytes());
+            None
+        },
+        Event::ColorRequest(index, format) => {
+            let dynamic_color =
+                term.upgrade().and_then(|term| term.lock().colors()[index]);
+            let color = dynamic_color.unwrap_or_else(|| {
+                let color = reply_context
+                    .lock()
+                    .unwrap()
+                    .theme
+                    .get_color_by_index(index);
+                Rgb {
+                    r: color.r(),
+                    g: color.g(),
+                    b: color.b(),
+                }
+            });
+            reply_context
+                .lock()
+                .unwrap()
+                .notifier
+                .notify(format(color).into_bytes());
+            None
+        },
+        Event::TextAreaSizeRequest(format) => {
+            let reply_context = reply_context.lock().unwrap();
+            reply_context
+                .notifier
+                .notify(format(reply_context.window_size).into_bytes());
+            None
+        },
+        event => Some(event),
+    }
+}
+
 /// Copied from alacritty/src/display/hint.rs:
 /// Iterate over all visible regex matches.
 fn visible_regex_match_iter<'a>(
@@ -539,30 +1687,76 @@ fn visible_regex_match_iter<'a>(
 }
 
 pub struct RenderableContent {
-    pub grid: Grid<Cell>,
+    /// Cells of the viewport, one row per screen line from the top.
+    pub rows: Vec<Row<Cell>>,
+    pub display_offset: usize,
+    pub cursor_point: Point,
     pub hovered_hyperlink: Option<RangeInclusive<Point>>,
+    pub hovered_osc8_hyperlink: Option<Hyperlink>,
+    pub hovered_link_style: LinkHoverStyle,
     pub selectable_range: Option<SelectionRange>,
     pub cursor: Cell,
+    pub cursor_style: CursorStyle,
+    pub vi_mode_cursor: Point,
     pub terminal_mode: TermMode,
     pub terminal_size: TerminalSize,
+    /// Matches in and around the viewport, sorted and not overlapping.
+    pub search_matches: Vec<Match>,
+    pub focused_search_match: Option<Match>,
+    /// The pattern being typed in vi mode, after its `/` or `?`.
+    pub vi_search_prompt: Option<String>,
+    pub hint_labels: Vec<HintLabel>,
 }
 
 impl Default for RenderableContent {
     fn default() -> Self {
         Self {
-            grid: Grid::new(0, 0, 0),
+            rows: vec![],
+            display_offset: 0,
+            cursor_point: Point::default(),
             hovered_hyperlink: None,
+            hovered_osc8_hyperlink: None,
+            hovered_link_style: LinkHoverStyle::default(),
             selectable_range: None,
             cursor: Cell::default(),
+            cursor_style: CursorStyle::default(),
+            vi_mode_cursor: Point::default(),
             terminal_mode: TermMode::empty(),
             terminal_size: TerminalSize::default(),
+            search_matches: vec![],
+            focused_search_match: None,
+            vi_search_prompt: None,
+            hint_labels: vec![],
         }
     }
 }
 
+impl RenderableContent {
+    /// Iterates over the viewport cells along with their grid points.
+    pub fn display_iter(&self) -> impl Iterator<Item = Indexed<&Cell>> {
+        let display_offset = self.display_offset as i32;
+        self.rows.iter().enumerate().flat_map(move |(line, row)| {
+            let line = Line(line as i32 - display_offset);
+            row.into_iter()
+                .enumerate()
+                .map(move |(column, cell)| Indexed {
+                    point: Point::new(line, Column(column)),
+                    cell,
+                })
+        })
+    }
+
+    /// Returns the cell at a grid point if it is inside the viewport.
+    pub fn cell(&self, point: Point) -> Option<&Cell> {
+        let line = usize::try_from(point.line.0 + self.display_offset as i32);
+        let row = self.rows.get(line.ok()?)?;
+        (point.column.0 < row.len()).then(|| &row[point.column])
+    }
+}
+
 impl Drop for TerminalBackend {
     fn drop(&mut self) {
-        let _ = self.notifier.0.send(Msg::Shutdown);
+        let _ = self.session.notifier.0.send(Msg::Shutdown);
     }
 }
 
@@ -574,3 +1768,179 @@ impl EventListener for EventProxy {
         let _ = self.0.send(event.clone());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        escape_regex, format_paste, RenderableContent, RepaintThrottle,
+    };
+    #[cfg(unix)]
+    use super::{
+        BackendCommand, BackendSettings, ChildStatus, LinkAction, PtyEvent,
+        TerminalBackend, ViAction,
+    };
+    use alacritty_terminal::grid::Row;
+    use alacritty_terminal::index::{Column, Line, Point};
+    #[cfg(unix)]
+    use alacritty_terminal::vte::ansi::Processor;
+    #[cfg(unix)]
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn format_paste_normalizes_newlines() {
+        assert_eq!(format_paste("a\nb\r\nc", false), b"a\rb\rc".to_vec());
+    }
+
+    #[test]
+    fn format_paste_wraps_bracketed_text() {
+        assert_eq!(
+            format_paste("echo 1\necho 2", true),
+            b"\x1b[200~echo 1\necho 2\x1b[201~".to_vec()
+        );
+    }
+
+    #[test]
+    fn format_paste_strips_embedded_terminators() {
+        assert_eq!(
+            format_paste("a\x1b[201~b\x1b[200~c", true),
+            b"\x1b[200~abc\x1b[201~".to_vec()
+        );
+    }
+
+    #[test]
+    fn escape_regex_escapes_meta_characters() {
+        assert_eq!(escape_regex("a.b*(c)"), r"a\.b\*\(c\)");
+        assert_eq!(escape_regex("plain text"), "plain text");
+    }
+
+    #[test]
+    fn cell_maps_grid_points_into_scrolled_viewport() {
+        let content = RenderableContent {
+            rows: vec![Row::new(4), Row::new(4)],
+            display_offset: 3,
+            ..Default::default()
+        };
+        assert!(content.cell(Point::new(Line(-3), Column(0))).is_some());
+        assert!(content.cell(Point::new(Line(-2), Column(3))).is_some());
+        assert!(content.cell(Point::new(Line(-4), Column(0))).is_none());
+        assert!(content.cell(Point::new(Line(-1), Column(0))).is_none());
+        assert!(content.cell(Point::new(Line(-3), Column(4))).is_none());
+    }
+
+    #[test]
+    fn repaint_throttle_spaces_repaints() {
+        let mut throttle = RepaintThrottle::new(10);
+        let start = Instant::now();
+        let ms = |ms| start + Duration::from_millis(ms);
+        assert_eq!(throttle.repaint_delay(ms(0)), Duration::ZERO);
+        assert_eq!(throttle.repaint_delay(ms(40)), Duration::from_millis(60));
+        assert_eq!(throttle.repaint_delay(ms(90)), Duration::from_millis(10));
+        assert_eq!(throttle.repaint_delay(ms(110)), Duration::from_millis(90));
+        assert_eq!(throttle.repaint_delay(ms(350)), Duration::ZERO);
+    }
+
+    #[cfg(unix)]
+    fn wait_for_exit(receiver: &Receiver<(u64, PtyEvent)>) {
+        loop {
+            match receiver.recv_timeout(Duration::from_secs(5)) {
+                Ok((_, PtyEvent::Exit)) => break,
+                Ok(_) => {},
+                Err(err) => panic!("child did not exit: {}", err),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn spawn_exiting_shell(sender: Sender<(u64, PtyEvent)>) -> TerminalBackend {
+        TerminalBackend::new(
+            0,
+            egui::Context::default(),
+            sender,
+            BackendSettings {
+                shell: "/bin/sh".to_string(),
+                args: vec!["-c".to_string(), "exit 3".to_string()],
+                ..BackendSettings::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn child_status_reports_exit_code() {
+        let (sender, receiver) = mpsc::channel();
+        let backend = spawn_exiting_shell(sender);
+        assert!(backend.child_pid().is_some());
+
+        wait_for_exit(&receiver);
+        assert_eq!(backend.child_status(), ChildStatus::Exited(3));
+        assert!(!backend.is_child_alive());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn respawn_keeps_scrollback_above_separator() {
+        let (sender, receiver) = mpsc::channel();
+        let mut backend = spawn_exiting_shell(sender);
+        wait_for_exit(&receiver);
+
+        backend.respawn(true).unwrap();
+        wait_for_exit(&receiver);
+        assert_eq!(backend.child_status(), ChildStatus::Exited(3));
+        let terminal = backend.term.lock();
+        assert_eq!(terminal.grid()[Line(0)][Column(0)].c, '\u{2500}');
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn vi_search_moves_cursor_to_match_from_cursor() {
+        let (sender, receiver) = mpsc::channel();
+        let mut backend = spawn_exiting_shell(sender);
+        wait_for_exit(&receiver);
+        {
+            let mut terminal = backend.term.lock();
+            let mut parser: Processor = Processor::new();
+            parser.advance(&mut *terminal, b"\x1b[2J\x1b[Hfoo bar\r\nbaz Foo");
+        }
+        backend.process_command(BackendCommand::ToggleViMode);
+        backend.term.lock().vi_mode_cursor.point =
+            Point::new(Line(0), Column(0));
+
+        let mut search = |action, pattern: &str| {
+            backend.process_command(BackendCommand::ViAction(action));
+            backend.process_command(BackendCommand::ViSearchInput(
+                pattern.to_string(),
+            ));
+            backend.process_command(BackendCommand::ViSearchConfirm);
+            backend.term.lock().vi_mode_cursor.point
+        };
+        assert_eq!(
+            search(ViAction::SearchForward, "foo"),
+            Point::new(Line(1), Column(4))
+        );
+        // Case sensitive, so the search wraps around past "foo".
+        assert_eq!(
+            search(ViAction::SearchBackward, "Foo"),
+            Point::new(Line(1), Column(4))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn link_hover_clamps_points_outside_the_grid() {
+        let (sender, receiver) = mpsc::channel();
+        let mut backend = spawn_exiting_shell(sender);
+        wait_for_exit(&receiver);
+
+        for point in [
+            Point::new(Line(1000), Column(1000)),
+            Point::new(Line(-1000), Column(0)),
+        ] {
+            backend.process_command(BackendCommand::ProcessLink(
+                LinkAction::Hover,
+                point,
+            ));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use alacritty_terminal::event::{OnResize, WindowSize};
+use alacritty_terminal::tty::{ChildEvent, EventedPty, EventedReadWrite, Pty};
+use polling::{Event, PollMode, Poller};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// OSC sequences longer than this are not OSC 7 and are skipped.
+const MAX_OSC_LEN: usize = 4096;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Picks the working directory out of OSC 7 sequences, which alacritty
+/// ignores, as `ESC ] 7 ; file://host/path` followed by BEL or ST.
+#[derive(Debug, Default)]
+pub(crate) struct Osc7Parser {
+    state: State,
+    osc: Vec<u8>,
+}
+
+impl Osc7Parser {
+    /// Returns the last directory reported in `bytes`. Sequences may be
+    /// split across calls.
+    pub fn advance(&mut self, bytes: &[u8]) -> Option<PathBuf> {
+        let mut cwd = None;
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (State::Ground, 0x1b) => State::Escape,
+                (State::Ground, _) => State::Ground,
+                (State::Escape, b']') => {
+                    self.osc.clear();
+                    State::Osc
+                },
+                (State::Escape, 0x1b) => State::Escape,
+                (State::Escape, _) => State::Ground,
+                (State::Osc, 0x07) | (State::OscEscape, b'\\') => {
+                    cwd = parse_osc7(&self.osc).or(cwd);
+                    State::Ground
+                },
+                (State::Osc, 0x1b) => State::OscEscape,
+                (State::Osc, _) => {
+                    if self.osc.len() < MAX_OSC_LEN {
+                        self.osc.push(byte);
+                    }
+                    State::Osc
+                },
+                (State::OscEscape, b']') => {
+                    self.osc.clear();
+                    State::Osc
+                },
+                (State::OscEscape, _) => State::Ground,
+            };
+        }
+
+        cwd
+    }
+}
+
+fn parse_osc7(osc: &[u8]) -> Option<PathBuf> {
+    if osc.len() >= MAX_OSC_LEN {
+        return None;
+    }
+
+    let url = osc.strip_prefix(b"7;file://")?;
+    let path = &url[url.iter().position(|&byte| byte == b'/')?..];
+    let path = percent_decode(path)?;
+    Some(PathBuf::from(String::from_utf8_lossy(&path).into_owned()))
+}
+
+fn percent_decode(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut bytes = bytes.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [*bytes.next()?, *bytes.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            decoded.push(byte);
+        }
+    }
+
+    Some(decoded)
+}
+
+/// Reads the pty through a duplicate of its master, so the output can be
+/// scanned for OSC 7 before alacritty parses it.
+pub(crate) struct Osc7Reader {
+    file: File,
+    parser: Osc7Parser,
+    cwd: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl Read for Osc7Reader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.file.read(buf)?;
+        if let Some(cwd) = self.parser.advance(&buf[..len]) {
+            *self.cwd.lock().unwrap() = Some(cwd);
+        }
+
+        Ok(len)
+    }
+}
+
+/// A pty whose output updates `cwd` with the directories reported by the
+/// shell.
+pub(crate) struct Osc7Pty {
+    pty: Pty,
+    reader: Osc7Reader,
+}
+
+impl Osc7Pty {
+    pub fn new(pty: Pty, cwd: Arc<Mutex<Option<PathBuf>>>) -> io::Result<Self> {
+        let reader = Osc7Reader {
+            file: pty.file().try_clone()?,
+            parser: Osc7Parser::default(),
+            cwd,
+        };
+        Ok(Self { pty, reader })
+    }
+}
+
+impl EventedReadWrite for Osc7Pty {
+    type Reader = Osc7Reader;
+    type Writer = File;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        // SAFETY: the pty is owned by self, so it outlives its registration
+        // as long as self does.
+        unsafe { self.pty.register(poll, interest, mode) }
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Osc7Reader {
+        &mut self.reader
+    }
+
+    fn writer(&mut self) -> &mut File {
+        self.pty.writer()
+    }
+}
+
+impl EventedPty for Osc7Pty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl OnResize for Osc7Pty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Osc7Parser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parses_bel_and_st_terminated_sequences() {
+        let mut parser = Osc7Parser::default();
+        assert_eq!(
+            parser.advance(b"a\x1b]7;file://host/tmp/a\x07b"),
+            Some(PathBuf::from("/tmp/a"))
+        );
+        assert_eq!(
+            parser.advance(b"\x1b]7;file://host/tmp/b\x1b\\"),
+            Some(PathBuf::from("/tmp/b"))
+        );
+        assert_eq!(parser.advance(b"\x1b]0;title\x07"), None);
+    }
+
+    #[test]
+    fn parses_split_percent_encoded_sequence() {
+        let mut parser = Osc7Parser::default();
+        assert_eq!(parser.advance(b"\x1b]7;file:///home/my%2"), None);
+        assert_eq!(
+            parser.advance(b"0dir\x07"),
+            Some(PathBuf::from("/home/my dir"))
+        );
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(unix)]
+use std::fs::File;
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::os::fd::AsRawFd;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildStatus {
+    Running,
+    Exited(i32),
+    /// The child ended without an exit code, which on Unix means it was
+    /// killed by a signal.
+    Terminated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub command_line: Vec<String>,
+    /// `None` when the process belongs to another user.
+    pub cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// `SIGINT`, what Ctrl+C sends.
+    Interrupt,
+    /// `SIGTERM`
+    Terminate,
+    /// `SIGKILL`
+    Kill,
+    /// `SIGHUP`, what closing a terminal sends.
+    Hangup,
+}
+
+#[cfg(unix)]
+impl Signal {
+    fn as_raw(self) -> libc::c_int {
+        match self {
+            Signal::Interrupt => libc::SIGINT,
+            Signal::Terminate => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Hangup => libc::SIGHUP,
+        }
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn kill(pid: u32, signal: Signal) -> io::Result<()> {
+    // SAFETY: kill only takes plain integers.
+    let status = unsafe { libc::kill(pid as libc::pid_t, signal.as_raw()) };
+    if status == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn kill_process_group(pgid: u32, signal: Signal) -> io::Result<()> {
+    // SAFETY: killpg only takes plain integers.
+    let status = unsafe { libc::killpg(pgid as libc::pid_t, signal.as_raw()) };
+    if status == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn foreground_process_group(pty: &File) -> io::Result<u32> {
+    // SAFETY: the fd is kept open by `pty` for the duration of the call.
+    let pgid = unsafe { libc::tcgetpgrp(pty.as_raw_fd()) };
+    if pgid == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(pgid as u32)
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn process_info(pid: u32) -> Option<ProcessInfo> {
+    let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+    let name = std::fs::read_to_string(proc_dir.join("comm")).ok()?;
+    let command_line = std::fs::read(proc_dir.join("cmdline"))
+        .ok()?
+        .split(|&byte| byte == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+
+    Some(ProcessInfo {
+        pid,
+        name: name.trim_end().to_string(),
+        command_line,
+        cwd: std::fs::read_link(proc_dir.join("cwd")).ok(),
+    })
+}
+
+/// Process details are read from `/proc`, which only Linux has.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn process_info(_pid: u32) -> Option<ProcessInfo> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn process_info_reads_own_process() {
+        let info = super::process_info(std::process::id()).unwrap();
+        assert!(!info.name.is_empty());
+        assert!(!info.command_line.is_empty());
+        assert_eq!(info.cwd, std::env::current_dir().ok());
+    }
+}
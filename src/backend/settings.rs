@@ -1,16 +1,261 @@
-const DEFAULT_SHELL: &str = "/bin/bash";
+use super::link::LinkRule;
+use crate::cursor::CursorStyle;
+use alacritty_terminal::term::{self, SEMANTIC_ESCAPE_CHARS};
+use alacritty_terminal::tty;
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::ffi::CStr;
+use std::path::PathBuf;
+
+const FALLBACK_SHELL: &str = "/bin/sh";
+const DEFAULT_MAX_FRAME_RATE: u32 = 60;
+const DEFAULT_SCROLLING_HISTORY: usize = 10000;
+const DEFAULT_TERM: &str = "xterm-256color";
+const DEFAULT_COLOR_TERM: &str = "truecolor";
+
+pub type Osc52 = term::Osc52;
+
+/// How OSC 52 clipboard read requests from the application are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClipboardReadPolicy {
+    /// Answer with the system clipboard, which the view requests from egui
+    /// and receives in the next frame. Reads of the primary selection are
+    /// answered with the text last stored there through OSC 52.
+    Allow,
+    /// Ignore read requests.
+    #[default]
+    Deny,
+    /// Forward `PtyEvent::ClipboardLoad` to the application to decide. It
+    /// replies with `TerminalBackend::answer_clipboard_load`.
+    Ask,
+}
+
+/// Options passed through to the alacritty terminal. They can be changed
+/// on a live backend with `TerminalBackend::set_terminal_config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalConfig {
+    /// Maximum number of lines kept in the scrollback.
+    pub scrolling_history: usize,
+    /// Characters that end a semantic selection.
+    pub semantic_escape_chars: String,
+    pub kitty_keyboard: bool,
+    /// OSC 52 clipboard sequences accepted from the application. Accepted
+    /// reads are still subject to the clipboard read policy.
+    pub osc52: Osc52,
+    /// Cursor style in vi mode. The default cursor style is used if unset.
+    pub vi_mode_cursor_style: Option<CursorStyle>,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            scrolling_history: DEFAULT_SCROLLING_HISTORY,
+            semantic_escape_chars: SEMANTIC_ESCAPE_CHARS.to_string(),
+            kitty_keyboard: false,
+            osc52: Osc52::CopyPaste,
+            vi_mode_cursor_style: None,
+        }
+    }
+}
+
+impl TerminalConfig {
+    /// Copies the options into `config`, keeping its default cursor style
+    /// which is set by the view.
+    pub(crate) fn apply_to(&self, config: &mut term::Config) {
+        config.scrolling_history = self.scrolling_history;
+        config.semantic_escape_chars = self.semantic_escape_chars.clone();
+        config.kitty_keyboard = self.kitty_keyboard;
+        config.osc52 = self.osc52;
+        config.vi_mode_cursor_style = self.vi_mode_cursor_style;
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct BackendSettings {
+    /// Defaults to the user's shell, see `default_shell`.
     pub shell: String,
     pub args: Vec<String>,
+    /// Starts the shell as a login shell so profile files are sourced.
+    /// The pty can't be spawned with a `-` prefixed argv0, so `-l` is
+    /// passed before `args` instead. bash, zsh, fish and dash accept it,
+    /// while csh and tcsh only do when it is their sole argument.
+    pub login_shell: bool,
+    /// Directory the shell starts in, the current one of the process if
+    /// unset.
+    pub working_directory: Option<PathBuf>,
+    /// Variables added to the shell environment. They take precedence over
+    /// `term`, `color_term` and `term_program`.
+    pub env: HashMap<String, String>,
+    /// Variables removed from the inherited environment, even if set above.
+    /// The shell is then started through `env -u`, which needs a Unix
+    /// `env` utility.
+    pub env_remove: Vec<String>,
+    /// Value of `TERM`.
+    pub term: String,
+    /// Value of `COLORTERM`, inherited from the process if unset.
+    pub color_term: Option<String>,
+    /// Value of `TERM_PROGRAM`, inherited from the process if unset.
+    pub term_program: Option<String>,
+    pub clipboard_read_policy: ClipboardReadPolicy,
+    /// Checked in order, the first rule matching the hovered text wins.
+    pub link_rules: Vec<LinkRule>,
+    /// Repaints requested for terminal output are coalesced to at most
+    /// this many per second.
+    pub max_frame_rate: u32,
+    pub terminal_config: TerminalConfig,
 }
 
 impl Default for BackendSettings {
     fn default() -> Self {
         Self {
-            shell: DEFAULT_SHELL.to_string(),
+            shell: default_shell(),
             args: vec![],
+            login_shell: false,
+            working_directory: None,
+            env: HashMap::new(),
+            env_remove: vec![],
+            term: DEFAULT_TERM.to_string(),
+            color_term: Some(DEFAULT_COLOR_TERM.to_string()),
+            term_program: None,
+            clipboard_read_policy: ClipboardReadPolicy::default(),
+            link_rules: vec![LinkRule::url()],
+            max_frame_rate: DEFAULT_MAX_FRAME_RATE,
+            terminal_config: TerminalConfig::default(),
+        }
+    }
+}
+
+impl BackendSettings {
+    pub(crate) fn pty_options(&self) -> tty::Options {
+        let mut env = HashMap::from([("TERM".to_string(), self.term.clone())]);
+        if let Some(color_term) = &self.color_term {
+            env.insert("COLORTERM".to_string(), color_term.clone());
+        }
+        if let Some(term_program) = &self.term_program {
+            env.insert("TERM_PROGRAM".to_string(), term_program.clone());
         }
+        env.extend(self.env.clone());
+
+        let login_args = self.login_shell.then(|| "-l".to_string());
+        let args = login_args.into_iter().chain(self.args.iter().cloned());
+        let shell = if self.env_remove.is_empty() {
+            tty::Shell::new(self.shell.clone(), args.collect())
+        } else {
+            let args = self
+                .env_remove
+                .iter()
+                .flat_map(|name| ["-u".to_string(), name.clone()])
+                .chain([self.shell.clone()])
+                .chain(args)
+                .collect();
+            tty::Shell::new("env".to_string(), args)
+        };
+
+        tty::Options {
+            shell: Some(shell),
+            working_directory: self.working_directory.clone(),
+            env,
+            ..tty::Options::default()
+        }
+    }
+}
+
+/// Resolves the user's shell from `$SHELL`, then the passwd database,
+/// falling back to `/bin/sh`.
+pub fn default_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .or_else(passwd_shell)
+        .unwrap_or_else(|| FALLBACK_SHELL.to_string())
+}
+
+#[cfg(unix)]
+fn passwd_shell() -> Option<String> {
+    /// Entries served by NSS modules such as LDAP can be larger than the
+    /// suggested size, so the buffer grows up to this limit.
+    const MAX_BUF_LEN: usize = 1 << 20;
+
+    // SAFETY: sysconf only reads a configuration value.
+    let suggested_len = unsafe { libc::sysconf(libc::_SC_GETPW_R_SIZE_MAX) };
+    let mut buf =
+        vec![0; usize::try_from(suggested_len).unwrap_or(1024).max(1024)];
+    // SAFETY: all zeroes is a valid passwd, it is only read once filled in.
+    let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result = std::ptr::null_mut();
+    loop {
+        // SAFETY: the pointers are valid for the duration of the call and
+        // `buf.len()` is the size of the buffer.
+        let status = unsafe {
+            libc::getpwuid_r(
+                libc::getuid(),
+                &mut entry,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut result,
+            )
+        };
+        match status {
+            0 => break,
+            libc::ERANGE if buf.len() < MAX_BUF_LEN => {
+                buf.resize(buf.len() * 2, 0);
+            },
+            _ => return None,
+        }
+    }
+    if result.is_null() || entry.pw_shell.is_null() {
+        return None;
+    }
+
+    // SAFETY: `pw_shell` points to a nul terminated string in `buf`.
+    let shell = unsafe { CStr::from_ptr(entry.pw_shell) };
+    shell
+        .to_str()
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(not(unix))]
+fn passwd_shell() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BackendSettings;
+    use alacritty_terminal::tty;
+    use std::collections::HashMap;
+
+    #[test]
+    fn pty_options_env_overrides_term_variables() {
+        let settings = BackendSettings {
+            env: HashMap::from([("TERM".to_string(), "dumb".to_string())]),
+            term_program: Some("ide".to_string()),
+            ..BackendSettings::default()
+        };
+        let env = settings.pty_options().env;
+        assert_eq!(env["TERM"], "dumb");
+        assert_eq!(env["COLORTERM"], "truecolor");
+        assert_eq!(env["TERM_PROGRAM"], "ide");
+    }
+
+    #[test]
+    fn pty_options_passes_login_flag_first() {
+        let settings = BackendSettings {
+            shell: "/bin/zsh".to_string(),
+            args: vec!["-i".to_string()],
+            login_shell: true,
+            env_remove: vec!["PATH".to_string()],
+            ..BackendSettings::default()
+        };
+        let shell = settings.pty_options().shell.unwrap();
+        let expected = tty::Shell::new(
+            "env".to_string(),
+            ["-u", "PATH", "/bin/zsh", "-l", "-i"]
+                .map(String::from)
+                .to_vec(),
+        );
+        assert_eq!(shell, expected);
     }
 }
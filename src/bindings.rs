@@ -1,16 +1,42 @@
+use crate::backend::hint::{Hint, HintAction};
+use crate::backend::{ViAction, ViMotion};
 use crate::TerminalMode;
 use egui::{Key, Modifiers, PointerButton};
+use std::hash::{Hash, Hasher};
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BindingAction {
     Copy,
     Paste,
     Char(char),
     Esc(String),
     LinkOpen,
+    ToggleViMode,
+    ViMotion(ViMotion),
+    ViAction(ViAction),
+    Hint(Hint),
     Ignore,
 }
 
+// Hashed by hand since alacritty's ViMotion does not implement Hash.
+impl Hash for BindingAction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            BindingAction::Char(c) => c.hash(state),
+            BindingAction::Esc(seq) => seq.hash(state),
+            BindingAction::ViMotion(motion) => (*motion as u8).hash(state),
+            BindingAction::ViAction(action) => action.hash(state),
+            BindingAction::Hint(hint) => hint.hash(state),
+            BindingAction::Copy
+            | BindingAction::Paste
+            | BindingAction::LinkOpen
+            | BindingAction::ToggleViMode
+            | BindingAction::Ignore => {},
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum InputKind {
     KeyCode(Key),
@@ -90,9 +116,12 @@ impl Default for BindingsLayout {
 
 impl BindingsLayout {
     pub fn new() -> Self {
+        // Vi mode bindings go first to take precedence over the ones
+        // writing to the PTY while vi mode is active.
         let mut layout = Self {
-            layout: default_keyboard_bindings(),
+            layout: vi_mode_bindings(),
         };
+        layout.add_bindings(default_keyboard_bindings());
         layout.add_bindings(platform_keyboard_bindings());
         layout.add_bindings(mouse_default_bindings());
         layout
@@ -167,6 +196,8 @@ fn default_keyboard_bindings() -> Vec<(Binding<InputKind>, BindingAction)> {
         F18;       BindingAction::Esc("\x1b[32~".into());
         F19;       BindingAction::Esc("\x1b[33~".into());
         F20;       BindingAction::Esc("\x1b[34~".into());
+        Copy;      BindingAction::Copy;
+        Paste;     BindingAction::Paste;
         // APP_CURSOR Excluding
         End,        ~TerminalMode::APP_CURSOR; BindingAction::Esc("\x1b[F".into());
         Home,       ~TerminalMode::APP_CURSOR; BindingAction::Esc("\x1b[H".into());
@@ -285,7 +316,7 @@ fn default_keyboard_bindings() -> Vec<(Binding<InputKind>, BindingAction)> {
         L,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x0c');
         M,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x0d');
         N,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x0e');
-        O,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x0f');
+        O,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Hint(Hint::new(Hint::default_regex(), HintAction::Open));
         P,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x10');
         Q,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x11');
         R,        Modifiers::SHIFT | Modifiers::CTRL; BindingAction::Char('\x12');
@@ -316,6 +347,56 @@ fn default_keyboard_bindings() -> Vec<(Binding<InputKind>, BindingAction)> {
         ArrowDown,  Modifiers::SHIFT | Modifiers::CTRL | Modifiers::ALT; BindingAction::Esc("\x1b[1;8B".into());
         ArrowLeft,  Modifiers::SHIFT | Modifiers::CTRL | Modifiers::ALT; BindingAction::Esc("\x1b[1;8D".into());
         ArrowRight, Modifiers::SHIFT | Modifiers::CTRL | Modifiers::ALT; BindingAction::Esc("\x1b[1;8C".into());
+        Space,      Modifiers::SHIFT | Modifiers::CTRL; BindingAction::ToggleViMode;
+    )
+}
+
+fn vi_mode_bindings() -> Vec<(Binding<InputKind>, BindingAction)> {
+    generate_bindings!(
+        KeyboardBinding;
+        I,                                   +TerminalMode::VI; BindingAction::ToggleViMode;
+        C,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ToggleViMode;
+        Escape,                              +TerminalMode::VI; BindingAction::ViAction(ViAction::ClearSelection);
+        Y,                                   +TerminalMode::VI; BindingAction::Copy;
+        V,                                   +TerminalMode::VI; BindingAction::ViAction(ViAction::ToggleNormalSelection);
+        V,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViAction(ViAction::ToggleLineSelection);
+        V,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ToggleBlockSelection);
+        V,                 Modifiers::ALT,   +TerminalMode::VI; BindingAction::ViAction(ViAction::ToggleSemanticSelection);
+        G,                                   +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollToTop);
+        G,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollToBottom);
+        B,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollPageUp);
+        F,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollPageDown);
+        U,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollHalfPageUp);
+        D,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollHalfPageDown);
+        Y,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollLineUp);
+        E,                 Modifiers::CTRL,  +TerminalMode::VI; BindingAction::ViAction(ViAction::ScrollLineDown);
+        N,                                   +TerminalMode::VI; BindingAction::ViAction(ViAction::SearchNext);
+        N,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViAction(ViAction::SearchPrevious);
+        Slash,                               +TerminalMode::VI; BindingAction::ViAction(ViAction::SearchForward);
+        Questionmark,      Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViAction(ViAction::SearchBackward);
+        K,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Up);
+        J,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Down);
+        H,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Left);
+        L,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Right);
+        ArrowUp,                             +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Up);
+        ArrowDown,                           +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Down);
+        ArrowLeft,                           +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Left);
+        ArrowRight,                          +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Right);
+        Num0,                                +TerminalMode::VI; BindingAction::ViMotion(ViMotion::First);
+        Num4,              Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Last);
+        Num6,              Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::FirstOccupied);
+        H,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::High);
+        M,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Middle);
+        L,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Low);
+        B,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::SemanticLeft);
+        W,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::SemanticRight);
+        E,                                   +TerminalMode::VI; BindingAction::ViMotion(ViMotion::SemanticRightEnd);
+        B,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::WordLeft);
+        W,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::WordRight);
+        E,                 Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::WordRightEnd);
+        Num5,              Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::Bracket);
+        OpenCurlyBracket,  Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::ParagraphUp);
+        CloseCurlyBracket, Modifiers::SHIFT, +TerminalMode::VI; BindingAction::ViMotion(ViMotion::ParagraphDown);
     )
 }
 
@@ -334,6 +415,8 @@ fn platform_keyboard_bindings() -> Vec<(Binding<InputKind>, BindingAction)> {
         KeyboardBinding;
         C, Modifiers::SHIFT | Modifiers::COMMAND; BindingAction::Copy;
         V, Modifiers::SHIFT | Modifiers::COMMAND; BindingAction::Paste;
+        Insert, Modifiers::CTRL;                  BindingAction::Copy;
+        Insert, Modifiers::SHIFT;                 BindingAction::Paste;
     )
 }
 
@@ -347,6 +430,8 @@ fn mouse_default_bindings() -> Vec<(Binding<InputKind>, BindingAction)> {
 #[cfg(test)]
 mod tests {
     use super::{BindingAction, BindingsLayout, InputKind, KeyboardBinding};
+    use crate::backend::hint::{Hint, HintAction};
+    use crate::backend::ViMotion;
     use crate::bindings::MouseBinding;
     use crate::TerminalMode;
     use egui::{Key, Modifiers, PointerButton};
@@ -487,4 +572,49 @@ mod tests {
             assert_eq!(action, &found_action);
         }
     }
+
+    #[test]
+    fn get_action_prefers_vi_mode_bindings() {
+        let current_layout = BindingsLayout::default();
+        assert_eq!(
+            current_layout.get_action(
+                InputKind::KeyCode(Key::ArrowUp),
+                Modifiers::NONE,
+                TerminalMode::VI,
+            ),
+            BindingAction::ViMotion(ViMotion::Up)
+        );
+        assert_eq!(
+            current_layout.get_action(
+                InputKind::KeyCode(Key::ArrowUp),
+                Modifiers::NONE,
+                TerminalMode::empty(),
+            ),
+            BindingAction::Esc("\x1b[A".into())
+        );
+    }
+
+    #[test]
+    fn get_action_starts_hints_instead_of_shift_in() {
+        let current_layout = BindingsLayout::default();
+        assert_eq!(
+            current_layout.get_action(
+                InputKind::KeyCode(Key::O),
+                Modifiers::SHIFT | Modifiers::CTRL,
+                TerminalMode::empty(),
+            ),
+            BindingAction::Hint(Hint::new(
+                Hint::default_regex(),
+                HintAction::Open
+            ))
+        );
+        assert_eq!(
+            current_layout.get_action(
+                InputKind::KeyCode(Key::O),
+                Modifiers::CTRL,
+                TerminalMode::empty(),
+            ),
+            BindingAction::Char('\x0f')
+        );
+    }
 }
@@ -0,0 +1,38 @@
+use alacritty_terminal::vte::ansi;
+use std::time::Duration;
+
+pub type CursorShape = ansi::CursorShape;
+pub type CursorStyle = ansi::CursorStyle;
+
+const DEFAULT_BLINK_INTERVAL: Duration = Duration::from_millis(750);
+
+#[derive(Debug, Clone)]
+pub struct CursorSettings {
+    /// Shape used until the application requests another one via DECSCUSR.
+    pub shape: CursorShape,
+    /// Whether the cursor blinks until the application says otherwise.
+    pub blinking: bool,
+    pub blink_interval: Duration,
+    /// Draw a hollow block instead of the regular shape when unfocused.
+    pub unfocused_hollow: bool,
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self {
+            shape: CursorShape::Block,
+            blinking: false,
+            blink_interval: DEFAULT_BLINK_INTERVAL,
+            unfocused_hollow: true,
+        }
+    }
+}
+
+impl CursorSettings {
+    pub(crate) fn default_style(&self) -> CursorStyle {
+        CursorStyle {
+            shape: self.shape,
+            blinking: self.blinking,
+        }
+    }
+}
@@ -1,30 +1,45 @@
-use egui::{Context, FontId};
+use alacritty_terminal::term::cell::Flags;
+use egui::{Context, FontFamily, FontId};
 
 use crate::types::Size;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FontSettings {
     pub font_type: FontId,
+    pub bold_font_family: Option<FontFamily>,
+    pub italic_font_family: Option<FontFamily>,
+    pub bold_italic_font_family: Option<FontFamily>,
 }
 
 impl Default for FontSettings {
     fn default() -> Self {
         Self {
             font_type: FontId::monospace(14.0),
+            bold_font_family: None,
+            italic_font_family: None,
+            bold_italic_font_family: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+pub(crate) struct CellFont {
+    pub font_id: FontId,
+    /// Bold text is drawn twice, slightly apart.
+    pub synthetic_bold: bool,
+    pub synthetic_italics: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TerminalFont {
     font_type: FontId,
+    bold_font_family: Option<FontFamily>,
+    italic_font_family: Option<FontFamily>,
+    bold_italic_font_family: Option<FontFamily>,
 }
 
 impl Default for TerminalFont {
     fn default() -> Self {
-        Self {
-            font_type: FontSettings::default().font_type,
-        }
+        Self::new(FontSettings::default())
     }
 }
 
@@ -32,6 +47,9 @@ impl TerminalFont {
     pub fn new(settings: FontSettings) -> Self {
         Self {
             font_type: settings.font_type,
+            bold_font_family: settings.bold_font_family,
+            italic_font_family: settings.italic_font_family,
+            bold_italic_font_family: settings.bold_italic_font_family,
         }
     }
 
@@ -39,6 +57,46 @@ impl TerminalFont {
         self.font_type.clone()
     }
 
+    /// Returns the font for a cell with the given flags, and which styles
+    /// must be synthesized because no family is configured for them.
+    pub(crate) fn cell_font(&self, flags: Flags) -> CellFont {
+        let is_bold = flags.contains(Flags::BOLD);
+        let is_italic = flags.contains(Flags::ITALIC);
+        let (family, synthetic_bold, synthetic_italics) =
+            match (is_bold, is_italic) {
+                (true, true) => match (
+                    &self.bold_italic_font_family,
+                    &self.bold_font_family,
+                    &self.italic_font_family,
+                ) {
+                    (Some(family), _, _) => (Some(family), false, false),
+                    (None, Some(family), _) => (Some(family), false, true),
+                    (None, None, Some(family)) => (Some(family), true, false),
+                    (None, None, None) => (None, true, true),
+                },
+                (true, false) => match &self.bold_font_family {
+                    Some(family) => (Some(family), false, false),
+                    None => (None, true, false),
+                },
+                (false, true) => match &self.italic_font_family {
+                    Some(family) => (Some(family), false, false),
+                    None => (None, false, true),
+                },
+                (false, false) => (None, false, false),
+            };
+
+        let font_id = match family {
+            Some(family) => FontId::new(self.font_type.size, family.clone()),
+            None => self.font_type(),
+        };
+
+        CellFont {
+            font_id,
+            synthetic_bold,
+            synthetic_italics,
+        }
+    }
+
     pub fn font_measure(&self, ctx: &Context) -> Size {
         let (width, height) = ctx.fonts(|f| {
             (
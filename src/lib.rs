@@ -1,13 +1,26 @@
 mod backend;
 mod bindings;
+mod cursor;
 mod font;
+mod text;
 mod theme;
 mod types;
 mod view;
 
-pub use backend::settings::BackendSettings;
-pub use backend::{BackendCommand, PtyEvent, TerminalBackend, TerminalMode};
+pub use backend::hint::{Hint, HintAction};
+pub use backend::link::{
+    LinkHandler, LinkHoverStyle, LinkRule, SystemLinkHandler, OPEN_LINK_ACTION,
+};
+pub use backend::process::{ChildStatus, ProcessInfo, Signal};
+pub use backend::settings::{
+    default_shell, BackendSettings, ClipboardReadPolicy, Osc52, TerminalConfig,
+};
+pub use backend::{
+    BackendCommand, PtyEvent, SearchOptions, TerminalBackend, TerminalMode,
+    ViAction, ViMotion,
+};
 pub use bindings::{Binding, BindingAction, InputKind, KeyboardBinding};
+pub use cursor::{CursorSettings, CursorShape};
 pub use font::{FontSettings, TerminalFont};
 pub use theme::{ColorPalette, TerminalTheme};
 pub use view::TerminalView;
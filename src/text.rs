@@ -0,0 +1,259 @@
+use egui::epaint::text::Fonts;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, FontId, Galley, Painter};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub(crate) struct TextStyle {
+    pub font_id: FontId,
+    pub italics: bool,
+    pub synthetic_bold: bool,
+    pub color: Color32,
+}
+
+// Font sizes are never NaN, so the comparison is reflexive.
+impl Eq for TextStyle {}
+
+/// Consecutive cells of a row drawn with the same style.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TextRun {
+    column: usize,
+    cells: usize,
+    text: String,
+    style: TextStyle,
+    /// Wide glyphs and glyphs with another advance than the rest of their
+    /// font, such as ones taken from a fallback font, are kept in runs of
+    /// their own and centered in their cells.
+    is_single: bool,
+}
+
+impl TextRun {
+    /// Adds the text of a cell to the last run of the row when it
+    /// continues it with the same style, otherwise starts a new run.
+    /// Blank cells only extend runs, they never start one. `fits_cell`
+    /// tells whether a glyph can share a run, see `has_cell_advance`.
+    pub fn push_cell(
+        runs: &mut Vec<TextRun>,
+        column: usize,
+        c: char,
+        is_wide: bool,
+        style: TextStyle,
+        fits_cell: impl Fn(&FontId, char) -> bool,
+    ) {
+        let c = if c == '\t' { ' ' } else { c };
+        let is_blank = c == ' ';
+        if let Some(last) = runs.last_mut() {
+            let is_continued = !last.is_single
+                && !is_wide
+                && last.column + last.cells == column
+                && (is_blank || last.style == style);
+            if is_continued && fits_cell(&last.style.font_id, c) {
+                last.text.push(c);
+                last.cells += 1;
+                return;
+            }
+        }
+
+        if !is_blank {
+            runs.push(TextRun {
+                column,
+                cells: if is_wide { 2 } else { 1 },
+                text: c.to_string(),
+                is_single: is_wide || !fits_cell(&style.font_id, c),
+                style,
+            });
+        }
+    }
+}
+
+/// Whether a glyph advances as much as an 'm' of its font. Runs are laid
+/// out with the spacing that moves an 'm' to the next cell, which only
+/// keeps glyphs of that advance on the grid.
+pub(crate) fn has_cell_advance(
+    fonts: &Fonts,
+    font_id: &FontId,
+    c: char,
+) -> bool {
+    let advance = fonts.glyph_width(font_id, c);
+    (advance - fonts.glyph_width(font_id, 'm')).abs() < 0.01
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LaidOutRun {
+    pub column: usize,
+    pub cells: usize,
+    pub synthetic_bold: bool,
+    pub galley: Arc<Galley>,
+}
+
+/// Galleys of the rows drawn in the last frame, keyed by their runs, so
+/// unchanged rows are not laid out again.
+#[derive(Debug, Default)]
+pub(crate) struct RowTextCache {
+    pixels_per_point: f32,
+    cell_width: f32,
+    previous_frame: HashMap<Vec<TextRun>, Vec<LaidOutRun>>,
+    current_frame: HashMap<Vec<TextRun>, Vec<LaidOutRun>>,
+}
+
+impl RowTextCache {
+    /// Drops the rows that were not drawn in the previous frame.
+    pub fn begin_frame(&mut self, pixels_per_point: f32, cell_width: f32) {
+        if self.pixels_per_point != pixels_per_point
+            || self.cell_width != cell_width
+        {
+            self.pixels_per_point = pixels_per_point;
+            self.cell_width = cell_width;
+            self.current_frame.clear();
+        }
+
+        self.previous_frame = std::mem::take(&mut self.current_frame);
+    }
+
+    pub fn layout(
+        &mut self,
+        painter: &Painter,
+        runs: Vec<TextRun>,
+    ) -> &[LaidOutRun] {
+        let previous_frame = &mut self.previous_frame;
+        let cell_width = self.cell_width;
+        self.current_frame.entry(runs).or_insert_with_key(|runs| {
+            previous_frame
+                .remove(runs)
+                .unwrap_or_else(|| layout_runs(painter, runs, cell_width))
+        })
+    }
+}
+
+fn layout_runs(
+    painter: &Painter,
+    runs: &[TextRun],
+    cell_width: f32,
+) -> Vec<LaidOutRun> {
+    let pixels_per_point = painter.ctx().pixels_per_point();
+    runs.iter()
+        .map(|run| {
+            // egui rounds the pen position to pixels after each glyph, so
+            // the advance is rounded the same way.
+            let advance =
+                painter.fonts(|f| f.glyph_width(&run.style.font_id, 'm'));
+            let advance =
+                (advance * pixels_per_point).round() / pixels_per_point;
+            let format = TextFormat {
+                font_id: run.style.font_id.clone(),
+                color: run.style.color,
+                italics: run.style.italics,
+                extra_letter_spacing: cell_width - advance,
+                ..Default::default()
+            };
+
+            LaidOutRun {
+                column: run.column,
+                cells: run.cells,
+                synthetic_bold: run.style.synthetic_bold,
+                galley: painter.layout_job(LayoutJob::single_section(
+                    run.text.clone(),
+                    format,
+                )),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RowTextCache, TextRun, TextStyle};
+    use egui::{Color32, FontId};
+
+    /// Pretends private use area glyphs come from a fallback font with a
+    /// different advance.
+    fn push_cell(
+        runs: &mut Vec<TextRun>,
+        column: usize,
+        c: char,
+        is_wide: bool,
+        style: TextStyle,
+    ) {
+        TextRun::push_cell(runs, column, c, is_wide, style, |_, c| {
+            !('\u{e000}'..='\u{f8ff}').contains(&c)
+        });
+    }
+
+    fn style(color: Color32) -> TextStyle {
+        TextStyle {
+            font_id: FontId::monospace(14.0),
+            italics: false,
+            synthetic_bold: false,
+            color,
+        }
+    }
+
+    #[test]
+    fn push_cell_merges_cells_with_same_style() {
+        let mut runs = vec![];
+        for (column, c) in "ab c".chars().enumerate() {
+            push_cell(&mut runs, column, c, false, style(Color32::RED));
+        }
+        push_cell(&mut runs, 4, 'd', false, style(Color32::BLUE));
+        push_cell(&mut runs, 6, 'e', false, style(Color32::BLUE));
+
+        let texts: Vec<_> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, vec!["ab c", "d", "e"]);
+    }
+
+    #[test]
+    fn push_cell_keeps_wide_chars_apart() {
+        let mut runs = vec![];
+        push_cell(&mut runs, 0, 'a', false, style(Color32::RED));
+        push_cell(&mut runs, 1, '漢', true, style(Color32::RED));
+        push_cell(&mut runs, 3, 'b', false, style(Color32::RED));
+
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[1].cells, 2);
+    }
+
+    #[test]
+    fn push_cell_keeps_glyphs_of_other_widths_apart() {
+        let mut runs = vec![];
+        for (column, c) in "ab\u{f115}c d".chars().enumerate() {
+            push_cell(&mut runs, column, c, false, style(Color32::RED));
+        }
+
+        let texts: Vec<_> = runs.iter().map(|run| run.text.as_str()).collect();
+        assert_eq!(texts, vec!["ab", "\u{f115}", "c d"]);
+        assert_eq!(runs[1].column, 2);
+        assert_eq!(runs[2].column, 3);
+    }
+
+    #[test]
+    fn layout_places_glyphs_on_cells() {
+        let ctx = egui::Context::default();
+        let _ = ctx.run(Default::default(), |ctx| {
+            let painter = egui::Painter::new(
+                ctx.clone(),
+                egui::LayerId::background(),
+                egui::Rect::EVERYTHING,
+            );
+            let font_id = FontId::monospace(14.0);
+            let cell_width = ctx.fonts(|f| f.glyph_width(&font_id, 'm'));
+            let cell_width = cell_width.floor();
+            let mut runs = vec![];
+            for (column, c) in "abc def".chars().enumerate() {
+                push_cell(&mut runs, column, c, false, style(Color32::RED));
+            }
+
+            let mut cache = RowTextCache::default();
+            cache.begin_frame(ctx.pixels_per_point(), cell_width);
+            let laid_out = cache.layout(&painter, runs);
+            let positions: Vec<_> = laid_out[0].galley.rows[0]
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.pos.x)
+                .collect();
+            let expected: Vec<_> =
+                (0..7).map(|column| column as f32 * cell_width).collect();
+            assert_eq!(positions, expected);
+        });
+    }
+}
@@ -2,7 +2,7 @@ use alacritty_terminal::vte::ansi::{self, NamedColor};
 use egui::Color32;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ColorPalette {
     pub foreground: String,
     pub background: String,
@@ -32,6 +32,13 @@ pub struct ColorPalette {
     pub dim_magenta: String,
     pub dim_cyan: String,
     pub dim_white: String,
+    pub search_match_foreground: String,
+    pub search_match_background: String,
+    pub search_focused_match_foreground: String,
+    pub search_focused_match_background: String,
+    pub vi_cursor: String,
+    pub hint_foreground: String,
+    pub hint_background: String,
 }
 
 impl Default for ColorPalette {
@@ -65,11 +72,18 @@ impl Default for ColorPalette {
             dim_magenta: String::from("#704d68"),
             dim_cyan: String::from("#4d7770"),
             dim_white: String::from("#8e8e8e"),
+            search_match_foreground: String::from("#181818"),
+            search_match_background: String::from("#f4bf75"),
+            search_focused_match_foreground: String::from("#f8f8f8"),
+            search_focused_match_background: String::from("#ac4242"),
+            vi_cursor: String::from("#6a9fb5"),
+            hint_foreground: String::from("#181818"),
+            hint_background: String::from("#90a959"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TerminalTheme {
     palette: Box<ColorPalette>,
     ansi256_colors: HashMap<u8, Color32>,
@@ -203,6 +217,77 @@ impl TerminalTheme {
             },
         }
     }
+
+    /// Returns the foreground and background colors of a search match.
+    pub(crate) fn get_search_match_colors(
+        &self,
+        is_focused: bool,
+    ) -> (Color32, Color32) {
+        let (fg, bg) = if is_focused {
+            (
+                &self.palette.search_focused_match_foreground,
+                &self.palette.search_focused_match_background,
+            )
+        } else {
+            (
+                &self.palette.search_match_foreground,
+                &self.palette.search_match_background,
+            )
+        };
+
+        (
+            hex_to_color(fg).unwrap_or_else(|_| panic!("invalid color {}", fg)),
+            hex_to_color(bg).unwrap_or_else(|_| panic!("invalid color {}", bg)),
+        )
+    }
+
+    pub(crate) fn get_vi_cursor_color(&self) -> Color32 {
+        hex_to_color(&self.palette.vi_cursor).unwrap_or_else(|_| {
+            panic!("invalid color {}", self.palette.vi_cursor)
+        })
+    }
+
+    /// Returns the foreground and background colors of hint labels.
+    pub(crate) fn get_hint_colors(&self) -> (Color32, Color32) {
+        let fg = &self.palette.hint_foreground;
+        let bg = &self.palette.hint_background;
+        (
+            hex_to_color(fg).unwrap_or_else(|_| panic!("invalid color {}", fg)),
+            hex_to_color(bg).unwrap_or_else(|_| panic!("invalid color {}", bg)),
+        )
+    }
+
+    /// Resolves an index of alacritty's color table, as used by OSC color
+    /// queries, to the theme color.
+    pub(crate) fn get_color_by_index(&self, index: usize) -> Color32 {
+        const NAMED_COLORS: [NamedColor; 13] = [
+            NamedColor::Foreground,
+            NamedColor::Background,
+            NamedColor::Cursor,
+            NamedColor::DimBlack,
+            NamedColor::DimRed,
+            NamedColor::DimGreen,
+            NamedColor::DimYellow,
+            NamedColor::DimBlue,
+            NamedColor::DimMagenta,
+            NamedColor::DimCyan,
+            NamedColor::DimWhite,
+            NamedColor::BrightForeground,
+            NamedColor::DimForeground,
+        ];
+
+        if let Ok(index) = u8::try_from(index) {
+            return self.get_color(ansi::Color::Indexed(index));
+        }
+
+        match NAMED_COLORS.into_iter().find(|c| *c as usize == index) {
+            // The cursor is drawn with the foreground color of its cell.
+            Some(NamedColor::Cursor) | None => {
+                self.get_color(ansi::Color::Named(NamedColor::Foreground))
+            },
+            Some(color) => self.get_color(ansi::Color::Named(color)),
+        }
+    }
 }
 
 fn hex_to_color(hex: &str) -> anyhow::Result<Color32> {
@@ -1,39 +1,76 @@
+use alacritty_terminal::grid::Dimensions;
 use alacritty_terminal::index::Point as TerminalGridPoint;
-use alacritty_terminal::term::cell;
+use alacritty_terminal::term::cell::{self, Hyperlink};
 use alacritty_terminal::term::TermMode;
 use alacritty_terminal::vte::ansi::{Color, NamedColor};
-use egui::epaint::RectShape;
-use egui::{CornerRadius, Key};
+use egui::epaint::{RectShape, StrokeKind};
 use egui::Modifiers;
 use egui::MouseWheelUnit;
 use egui::Shape;
 use egui::Widget;
-use egui::{Align2, Painter, Pos2, Rect, Response, Stroke, Vec2};
+use egui::{Color32, Painter, Pos2, Rect, Response, Stroke, Vec2};
+use egui::{CornerRadius, Key};
 use egui::{Id, PointerButton};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use crate::backend::link::LinkHoverStyle;
+use crate::backend::process::ChildStatus;
 use crate::backend::BackendCommand;
-use crate::backend::TerminalBackend;
-use crate::backend::{LinkAction, MouseButton, SelectionType};
+use crate::backend::{LinkAction, MouseButton, SelectionType, ViAction};
+use crate::backend::{RenderableContent, TerminalBackend};
 use crate::bindings::Binding;
 use crate::bindings::{BindingAction, BindingsLayout, InputKind};
+use crate::cursor::{CursorSettings, CursorShape, CursorStyle};
 use crate::font::TerminalFont;
+use crate::text::{has_cell_advance, RowTextCache, TextRun, TextStyle};
 use crate::theme::TerminalTheme;
 use crate::types::Size;
 
 const EGUI_TERM_WIDGET_ID_PREFIX: &str = "egui_term::instance::";
+/// Horizontal offset of the second draw of synthesized bold text.
+const SYNTHETIC_BOLD_OFFSET: f32 = 1.0;
 
 #[derive(Debug, Clone)]
 enum InputAction {
     BackendCall(BackendCommand),
     WriteToClipboard(String),
+    RequestPaste,
     Ignore,
 }
 
 #[derive(Clone, Default)]
 pub struct TerminalViewState {
     is_dragged: bool,
+    is_focused: bool,
+    mouse_report_button: Option<PointerButton>,
+    is_paste_requested: bool,
+    is_clipboard_load_requested: bool,
     scroll_pixels: f32,
     current_mouse_position_on_grid: TerminalGridPoint,
+    cursor_style: Option<CursorStyle>,
+    cursor_blink_start: f64,
+    is_vi_scroll_to_top_pending: bool,
+    /// Shared so that cloning the state out of egui memory every frame
+    /// does not copy the laid out rows.
+    row_text_cache: Arc<Mutex<RowTextCache>>,
+    respawn_error: Option<String>,
+    shapes_cache: Option<(ShapesKey, Arc<Vec<Shape>>)>,
+}
+
+/// Everything besides the theme the shapes of a terminal depend on. The
+/// backend counts a theme change as a content change.
+#[derive(Clone, PartialEq)]
+struct ShapesKey {
+    content_version: u64,
+    rect: Rect,
+    pixels_per_point: f32,
+    font: TerminalFont,
+    is_focused: bool,
+    cursor_shape: CursorShape,
+    mouse_position: TerminalGridPoint,
+    is_exit_overlay_shown: bool,
+    respawn_error: Option<String>,
 }
 
 pub struct TerminalView<'a> {
@@ -43,7 +80,9 @@ pub struct TerminalView<'a> {
     backend: &'a mut TerminalBackend,
     font: TerminalFont,
     theme: TerminalTheme,
+    cursor: CursorSettings,
     bindings_layout: BindingsLayout,
+    has_exit_overlay: bool,
 }
 
 impl Widget for TerminalView<'_> {
@@ -58,8 +97,9 @@ impl Widget for TerminalView<'_> {
                 .unwrap_or_default()
         });
 
-        self.focus(&layout)
+        self.focus(&layout, &mut state)
             .resize(&layout)
+            .apply_settings()
             .process_input(&layout, &mut state)
             .show(&mut state, &layout, &painter);
 
@@ -82,7 +122,9 @@ impl<'a> TerminalView<'a> {
             backend,
             font: TerminalFont::default(),
             theme: TerminalTheme::default(),
+            cursor: CursorSettings::default(),
             bindings_layout: BindingsLayout::new(),
+            has_exit_overlay: false,
         }
     }
 
@@ -98,6 +140,12 @@ impl<'a> TerminalView<'a> {
         self
     }
 
+    #[inline]
+    pub fn set_cursor(mut self, cursor: CursorSettings) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
     #[inline]
     pub fn set_focus(mut self, has_focus: bool) -> Self {
         self.has_focus = has_focus;
@@ -110,6 +158,14 @@ impl<'a> TerminalView<'a> {
         self
     }
 
+    /// Shows how the shell exited over the terminal, and restarts it with
+    /// the scrollback kept when Enter is pressed.
+    #[inline]
+    pub fn set_exit_overlay(mut self, has_exit_overlay: bool) -> Self {
+        self.has_exit_overlay = has_exit_overlay;
+        self
+    }
+
     #[inline]
     pub fn add_bindings(
         mut self,
@@ -119,13 +175,20 @@ impl<'a> TerminalView<'a> {
         self
     }
 
-    fn focus(self, layout: &Response) -> Self {
+    fn focus(self, layout: &Response, state: &mut TerminalViewState) -> Self {
         if self.has_focus {
             layout.request_focus();
         } else {
             layout.surrender_focus();
         }
 
+        let is_focused = layout.has_focus();
+        if state.is_focused != is_focused {
+            state.is_focused = is_focused;
+            self.backend
+                .process_command(BackendCommand::FocusChange(is_focused));
+        }
+
         self
     }
 
@@ -138,39 +201,102 @@ impl<'a> TerminalView<'a> {
         self
     }
 
+    fn apply_settings(self) -> Self {
+        self.backend.set_theme(&self.theme);
+        self.backend
+            .process_command(BackendCommand::SetDefaultCursorStyle(
+                self.cursor.default_style(),
+            ));
+
+        self
+    }
+
     fn process_input(
         self,
         layout: &Response,
         state: &mut TerminalViewState,
     ) -> Self {
+        // The contents of a paste request arrive in the next frame, or not
+        // at all when the clipboard is empty.
+        let is_paste_requested = std::mem::take(&mut state.is_paste_requested);
+        // Clipboard reads allowed by the backend are answered the same way,
+        // with an empty reply when the clipboard is empty.
+        let is_clipboard_load_requested =
+            std::mem::take(&mut state.is_clipboard_load_requested);
+        if is_clipboard_load_requested {
+            let text = layout.ctx.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Paste(text) => Some(text.clone()),
+                    _ => None,
+                })
+            });
+            self.backend
+                .answer_pending_clipboard_loads(&text.unwrap_or_default());
+        }
+        if self.backend.has_pending_clipboard_loads() {
+            state.is_clipboard_load_requested = true;
+            layout
+                .ctx
+                .send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+        }
         if !layout.has_focus() || !layout.contains_pointer() {
             return self;
         }
 
         let modifiers = layout.ctx.input(|i| i.modifiers);
         let events = layout.ctx.input(|i| i.events.clone());
+        let is_exit_overlay_shown =
+            self.has_exit_overlay && !self.backend.is_child_alive();
         for event in events {
+            if is_exit_overlay_shown {
+                process_exit_overlay_event(state, self.backend, &event);
+                if matches!(
+                    event,
+                    egui::Event::Text(_) | egui::Event::Key { .. }
+                ) {
+                    continue;
+                }
+            }
+
             let mut input_actions = vec![];
 
             match event {
                 egui::Event::Text(_)
                 | egui::Event::Key { .. }
                 | egui::Event::Copy
+                | egui::Event::Cut
                 | egui::Event::Paste(_) => {
+                    if matches!(event, egui::Event::Paste(_))
+                        && is_clipboard_load_requested
+                        && !is_paste_requested
+                    {
+                        continue;
+                    }
+
+                    state.cursor_blink_start = layout.ctx.input(|i| i.time);
                     input_actions.push(process_keyboard_event(
                         event,
+                        state,
                         self.backend,
                         &self.bindings_layout,
                         modifiers,
+                        is_paste_requested,
                     ))
                 },
-                egui::Event::MouseWheel { unit, delta, .. } => input_actions
-                    .push(process_mouse_wheel(
+                egui::Event::MouseWheel {
+                    unit,
+                    delta,
+                    modifiers,
+                } => {
+                    input_actions = process_mouse_wheel(
                         state,
+                        self.backend,
                         self.font.font_type().size,
                         unit,
                         delta,
-                    )),
+                        &modifiers,
+                    )
+                },
                 egui::Event::PointerButton {
                     button,
                     pressed,
@@ -206,6 +332,25 @@ impl<'a> TerminalView<'a> {
                     },
                     InputAction::WriteToClipboard(data) => {
                         layout.ctx.copy_text(data);
+                        // Yanking ends the selection like in vim.
+                        if self
+                            .backend
+                            .last_content()
+                            .terminal_mode
+                            .contains(TermMode::VI)
+                        {
+                            self.backend.process_command(
+                                BackendCommand::ViAction(
+                                    ViAction::ClearSelection,
+                                ),
+                            );
+                        }
+                    },
+                    InputAction::RequestPaste => {
+                        state.is_paste_requested = true;
+                        layout.ctx.send_viewport_cmd(
+                            egui::ViewportCommand::RequestPaste,
+                        );
                     },
                     InputAction::Ignore => {},
                 }
@@ -221,21 +366,94 @@ impl<'a> TerminalView<'a> {
         layout: &Response,
         painter: &Painter,
     ) {
-        let content = self.backend.sync();
+        self.backend.sync();
+        let content = self.backend.last_content();
+        let cursor_shape =
+            visible_cursor_shape(&self.cursor, state, content, layout);
+        let hovered_osc8_hyperlink =
+            content.hovered_osc8_hyperlink.as_ref().filter(|hyperlink| {
+                content
+                    .cell(state.current_mouse_position_on_grid)
+                    .and_then(|cell| cell.hyperlink())
+                    .as_ref()
+                    == Some(hyperlink)
+            });
+        if let Some(hyperlink) = hovered_osc8_hyperlink {
+            layout
+                .clone()
+                .on_hover_text_at_pointer(hyperlink.uri().to_owned());
+        }
+
+        let is_exit_overlay_shown =
+            self.has_exit_overlay && !self.backend.is_child_alive();
+        let shapes_key = ShapesKey {
+            content_version: self.backend.content_version(),
+            rect: layout.rect,
+            pixels_per_point: painter.ctx().pixels_per_point(),
+            font: self.font.clone(),
+            is_focused: layout.has_focus(),
+            cursor_shape,
+            mouse_position: state.current_mouse_position_on_grid,
+            is_exit_overlay_shown,
+            respawn_error: state.respawn_error.clone(),
+        };
+        let is_cached = state
+            .shapes_cache
+            .as_ref()
+            .is_some_and(|(key, _)| *key == shapes_key);
+        if !is_cached {
+            let shapes = self.build_shapes(
+                state,
+                layout,
+                painter,
+                cursor_shape,
+                hovered_osc8_hyperlink,
+                is_exit_overlay_shown,
+            );
+            state.shapes_cache = Some((shapes_key, Arc::new(shapes)));
+        }
+
+        if let Some((_, shapes)) = &state.shapes_cache {
+            painter.extend(shapes.iter().cloned());
+        }
+    }
+
+    fn build_shapes(
+        &self,
+        state: &mut TerminalViewState,
+        layout: &Response,
+        painter: &Painter,
+        cursor_shape: CursorShape,
+        hovered_osc8_hyperlink: Option<&Hyperlink>,
+        is_exit_overlay_shown: bool,
+    ) -> Vec<Shape> {
+        let content = self.backend.last_content();
         let layout_min = layout.rect.min;
         let layout_max = layout.rect.max;
         let cell_height = content.terminal_size.cell_height as f32;
         let cell_width = content.terminal_size.cell_width as f32;
         let global_bg =
             self.theme.get_color(Color::Named(NamedColor::Background));
+        let is_vi_mode = content.terminal_mode.contains(TermMode::VI);
+        let cursor_point = if is_vi_mode {
+            content.vi_mode_cursor
+        } else {
+            content.cursor_point
+        };
 
+        let mut row_text_cache = state.row_text_cache.lock().unwrap();
+        row_text_cache
+            .begin_frame(painter.ctx().pixels_per_point(), cell_width);
+        let mut row_text_runs = vec![vec![]; content.rows.len()];
         let mut shapes = vec![Shape::Rect(RectShape::filled(
             Rect::from_min_max(layout_min, layout_max),
             CornerRadius::ZERO,
             global_bg,
         ))];
 
-        for indexed in content.grid.display_iter() {
+        // Cells are visited in grid order, so the matches are walked along.
+        let mut search_matches = content.search_matches.iter().peekable();
+        for indexed in content.display_iter() {
             let flags = indexed.cell.flags;
             let is_wide_char_spacer =
                 flags.contains(cell::Flags::WIDE_CHAR_SPACER);
@@ -243,12 +461,11 @@ impl<'a> TerminalView<'a> {
                 continue;
             }
 
-            let is_app_cursor_mode =
-                content.terminal_mode.contains(TermMode::APP_CURSOR);
+            let is_cursor = cursor_shape != CursorShape::Hidden
+                && cursor_point == indexed.point;
             let is_wide_char = flags.contains(cell::Flags::WIDE_CHAR);
             let is_inverse = flags.contains(cell::Flags::INVERSE);
-            let is_dim =
-                flags.intersects(cell::Flags::DIM | cell::Flags::DIM_BOLD);
+            let is_dim = flags.contains(cell::Flags::DIM);
             let is_selected = content
                 .selectable_range
                 .is_some_and(|r| r.contains(indexed.point));
@@ -256,11 +473,24 @@ impl<'a> TerminalView<'a> {
                 content.hovered_hyperlink.as_ref().is_some_and(|r| {
                     r.contains(&indexed.point)
                         && r.contains(&state.current_mouse_position_on_grid)
+                }) || hovered_osc8_hyperlink.is_some_and(|hyperlink| {
+                    indexed.cell.hyperlink().as_ref() == Some(hyperlink)
                 });
+            let is_focused_search_match = content
+                .focused_search_match
+                .as_ref()
+                .is_some_and(|m| m.contains(&indexed.point));
+            while search_matches
+                .next_if(|m| *m.end() < indexed.point)
+                .is_some()
+            {}
+            let is_search_match = is_focused_search_match
+                || search_matches
+                    .peek()
+                    .is_some_and(|m| m.contains(&indexed.point));
 
             let x = layout_min.x + (cell_width * indexed.point.column.0 as f32);
-            let line_num =
-                indexed.point.line.0 + content.grid.display_offset() as i32;
+            let line_num = indexed.point.line.0 + content.display_offset as i32;
             let y = layout_min.y + (cell_height * line_num as f32);
 
             let mut fg = self.theme.get_color(indexed.fg);
@@ -279,6 +509,17 @@ impl<'a> TerminalView<'a> {
                 std::mem::swap(&mut fg, &mut bg);
             }
 
+            if is_search_match && !is_selected {
+                (fg, bg) =
+                    self.theme.get_search_match_colors(is_focused_search_match);
+            }
+
+            let hovered_link_style =
+                is_hovered_hyperling.then_some(content.hovered_link_style);
+            if hovered_link_style == Some(LinkHoverStyle::Inverse) {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+
             if global_bg != bg {
                 shapes.push(Shape::Rect(RectShape::filled(
                     Rect::from_min_size(
@@ -292,93 +533,406 @@ impl<'a> TerminalView<'a> {
             }
 
             // Handle hovered hyperlink underline
-            if is_hovered_hyperling {
+            if hovered_link_style == Some(LinkHoverStyle::Underline) {
                 let underline_height = y + cell_height;
                 shapes.push(Shape::LineSegment {
                     points: [
                         Pos2::new(x, underline_height),
                         Pos2::new(x + cell_width, underline_height),
                     ],
-                    stroke: Stroke::new(cell_height * 0.15, fg).into(),
+                    stroke: Stroke::new(cell_height * 0.15, fg),
                 });
             }
 
             // Handle cursor rendering
-            if content.grid.cursor.point == indexed.point {
-                let cursor_color = self.theme.get_color(content.cursor.fg);
-                shapes.push(Shape::Rect(RectShape::filled(
+            if is_cursor {
+                let cursor_color = if is_vi_mode {
+                    self.theme.get_vi_cursor_color()
+                } else {
+                    self.theme.get_color(content.cursor.fg)
+                };
+                shapes.push(build_cursor_shape(
+                    cursor_shape,
                     Rect::from_min_size(
                         Pos2::new(x, y),
                         Vec2::new(cell_width, cell_height),
                     ),
-                    CornerRadius::default(),
                     cursor_color,
-                )));
+                ));
+            }
+
+            // Text is laid out per run of cells once the grid is walked
+            if is_cursor && cursor_shape == CursorShape::Block {
+                std::mem::swap(&mut fg, &mut bg);
+            }
+
+            let font = self.font.cell_font(flags);
+            TextRun::push_cell(
+                &mut row_text_runs[line_num as usize],
+                indexed.point.column.0,
+                indexed.c,
+                is_wide_char,
+                TextStyle {
+                    font_id: font.font_id,
+                    italics: font.synthetic_italics,
+                    synthetic_bold: font.synthetic_bold,
+                    color: fg,
+                },
+                |font_id, c| painter.fonts(|f| has_cell_advance(f, font_id, c)),
+            );
+
+            // Handle underline and strikethrough attributes
+            let line_width = (cell_height * 0.08).max(1.0);
+            if flags.intersects(cell::Flags::ALL_UNDERLINES) {
+                let underline_color = indexed
+                    .cell
+                    .underline_color()
+                    .map_or(fg, |c| self.theme.get_color(c));
+                shapes.extend(build_underline_shapes(
+                    flags,
+                    Rect::from_min_size(
+                        Pos2::new(x, y),
+                        Vec2::new(cell_width, cell_height),
+                    ),
+                    content.terminal_size.cell_width as f32,
+                    line_width,
+                    underline_color,
+                ));
             }
 
-            // Draw text content
-            if indexed.c != ' ' && indexed.c != '\t' {
-                if content.grid.cursor.point == indexed.point
-                    && is_app_cursor_mode
-                {
-                    std::mem::swap(&mut fg, &mut bg);
+            if flags.contains(cell::Flags::STRIKEOUT) {
+                let strikeout_y = y + cell_height / 2.0;
+                shapes.push(Shape::line_segment(
+                    [
+                        Pos2::new(x, strikeout_y),
+                        Pos2::new(x + cell_width, strikeout_y),
+                    ],
+                    Stroke::new(line_width, fg),
+                ));
+            }
+        }
+
+        let default_fg =
+            self.theme.get_color(Color::Named(NamedColor::Foreground));
+        for (line, runs) in row_text_runs.into_iter().enumerate() {
+            if runs.is_empty() {
+                continue;
+            }
+
+            let y = layout_min.y + (cell_height * line as f32);
+            for run in row_text_cache.layout(painter, runs) {
+                let width = cell_width * run.cells as f32;
+                let x = layout_min.x
+                    + (cell_width * run.column as f32)
+                    + (width - run.galley.size().x) / 2.0;
+                shapes.push(Shape::galley(
+                    Pos2::new(x, y),
+                    run.galley.clone(),
+                    default_fg,
+                ));
+                if run.synthetic_bold {
+                    shapes.push(Shape::galley(
+                        Pos2::new(x + SYNTHETIC_BOLD_OFFSET, y),
+                        run.galley.clone(),
+                        default_fg,
+                    ));
                 }
+            }
+        }
 
-                shapes.push(Shape::text(
-                    &painter.fonts(|c| c.clone()),
-                    Pos2 {
-                        x: x + (cell_width / 2.0),
-                        y,
-                    },
-                    Align2::CENTER_TOP,
-                    indexed.c,
+        let (hint_fg, hint_bg) = self.theme.get_hint_colors();
+        for hint in &content.hint_labels {
+            let line_num = hint.point.line.0 + content.display_offset as i32;
+            let y = layout_min.y + (cell_height * line_num as f32);
+            for (offset, c) in hint.label.chars().enumerate() {
+                let column = hint.point.column.0 + offset;
+                if column >= content.terminal_size.columns() {
+                    break;
+                }
+
+                let x = layout_min.x + (cell_width * column as f32);
+                shapes.push(Shape::Rect(RectShape::filled(
+                    Rect::from_min_size(
+                        Pos2::new(x, y),
+                        Vec2::new(cell_width, cell_height),
+                    ),
+                    CornerRadius::ZERO,
+                    hint_bg,
+                )));
+                let galley = painter.layout_no_wrap(
+                    c.to_string(),
                     self.font.font_type(),
-                    fg,
+                    hint_fg,
+                );
+                let text_x = x + (cell_width - galley.size().x) / 2.0;
+                shapes.push(Shape::galley(
+                    Pos2::new(text_x, y),
+                    galley,
+                    hint_fg,
                 ));
             }
         }
 
-        painter.extend(shapes);
+        if let Some(prompt) = &content.vi_search_prompt {
+            let y = layout_min.y
+                + cell_height
+                    * (content.terminal_size.screen_lines() - 1) as f32;
+            shapes.push(Shape::Rect(RectShape::filled(
+                Rect::from_min_max(Pos2::new(layout_min.x, y), layout_max),
+                CornerRadius::ZERO,
+                hint_bg,
+            )));
+            shapes.push(Shape::galley(
+                Pos2::new(layout_min.x, y),
+                painter.layout_no_wrap(
+                    prompt.clone(),
+                    self.font.font_type(),
+                    hint_fg,
+                ),
+                hint_fg,
+            ));
+        }
+
+        if is_exit_overlay_shown {
+            shapes.extend(self.build_exit_overlay_shapes(
+                state,
+                layout.rect,
+                painter,
+            ));
+        }
+
+        shapes
+    }
+
+    fn build_exit_overlay_shapes(
+        &self,
+        state: &TerminalViewState,
+        rect: Rect,
+        painter: &Painter,
+    ) -> Vec<Shape> {
+        let status = match self.backend.child_status() {
+            ChildStatus::Exited(code) => {
+                format!("Process exited with code {}", code)
+            },
+            _ => "Process was terminated".to_string(),
+        };
+        let message = match &state.respawn_error {
+            Some(err) => format!("{}\n{}", status, err),
+            None => format!("{} \u{2014} press Enter to restart", status),
+        };
+        let fg = self.theme.get_color(Color::Named(NamedColor::Foreground));
+        let bg = self.theme.get_color(Color::Named(NamedColor::Background));
+        let galley =
+            painter.layout(message, self.font.font_type(), fg, rect.width());
+        let padding = Vec2::splat(self.font.font_type().size);
+        let message_rect = Rect::from_center_size(
+            rect.center(),
+            galley.size() + padding * 2.0,
+        );
+
+        vec![
+            Shape::Rect(RectShape::filled(
+                rect,
+                CornerRadius::ZERO,
+                Color32::from_black_alpha(128),
+            )),
+            Shape::Rect(RectShape::filled(
+                message_rect,
+                CornerRadius::ZERO,
+                bg,
+            )),
+            Shape::galley(message_rect.min + padding, galley, fg),
+        ]
+    }
+}
+
+fn visible_cursor_shape(
+    settings: &CursorSettings,
+    state: &mut TerminalViewState,
+    content: &RenderableContent,
+    layout: &Response,
+) -> CursorShape {
+    if !content
+        .terminal_mode
+        .intersects(TermMode::SHOW_CURSOR | TermMode::VI)
+    {
+        return CursorShape::Hidden;
+    }
+
+    // The style changes on DECSCUSR and on CursorBlinkingChange events,
+    // both of which restart the blink cycle.
+    let now = layout.ctx.input(|i| i.time);
+    let style = content.cursor_style;
+    if state.cursor_style != Some(style) {
+        state.cursor_style = Some(style);
+        state.cursor_blink_start = now;
+    }
+
+    if !layout.has_focus() {
+        return if settings.unfocused_hollow {
+            CursorShape::HollowBlock
+        } else {
+            style.shape
+        };
+    }
+
+    if style.blinking && !settings.blink_interval.is_zero() {
+        let interval = settings.blink_interval.as_secs_f64();
+        let elapsed = (now - state.cursor_blink_start).max(0.0);
+        layout.ctx.request_repaint_after(Duration::from_secs_f64(
+            interval - elapsed % interval,
+        ));
+
+        if (elapsed / interval) as u64 % 2 == 1 {
+            return CursorShape::Hidden;
+        }
+    }
+
+    style.shape
+}
+
+fn build_cursor_shape(
+    shape: CursorShape,
+    cell_rect: Rect,
+    color: Color32,
+) -> Shape {
+    let thickness = (cell_rect.width() * 0.15).max(1.0);
+    match shape {
+        CursorShape::Block => {
+            Shape::Rect(RectShape::filled(cell_rect, CornerRadius::ZERO, color))
+        },
+        CursorShape::Beam => Shape::Rect(RectShape::filled(
+            Rect::from_min_size(
+                cell_rect.min,
+                Vec2::new(thickness, cell_rect.height()),
+            ),
+            CornerRadius::ZERO,
+            color,
+        )),
+        CursorShape::Underline => Shape::Rect(RectShape::filled(
+            Rect::from_min_max(
+                Pos2::new(cell_rect.min.x, cell_rect.max.y - thickness),
+                cell_rect.max,
+            ),
+            CornerRadius::ZERO,
+            color,
+        )),
+        CursorShape::HollowBlock => Shape::Rect(RectShape::stroke(
+            cell_rect,
+            CornerRadius::ZERO,
+            Stroke::new(thickness.min(2.0), color),
+            StrokeKind::Inside,
+        )),
+        CursorShape::Hidden => Shape::Noop,
+    }
+}
+
+fn build_underline_shapes(
+    flags: cell::Flags,
+    cell_rect: Rect,
+    period: f32,
+    line_width: f32,
+    color: Color32,
+) -> Vec<Shape> {
+    let stroke = Stroke::new(line_width, color);
+    let underline_y = cell_rect.max.y - line_width;
+    let underline = [
+        Pos2::new(cell_rect.min.x, underline_y),
+        Pos2::new(cell_rect.max.x, underline_y),
+    ];
+
+    if flags.contains(cell::Flags::UNDERCURL) {
+        // One wave per cell keeps the curl continuous between cells.
+        let amplitude = line_width;
+        let base_y = underline_y - amplitude;
+        let steps = (cell_rect.width() / period * 8.0).ceil() as usize;
+        let points = (0..=steps)
+            .map(|step| {
+                let dx = cell_rect.width() * step as f32 / steps as f32;
+                let phase = dx / period * std::f32::consts::TAU;
+                Pos2::new(
+                    cell_rect.min.x + dx,
+                    base_y + amplitude * phase.sin(),
+                )
+            })
+            .collect();
+
+        vec![Shape::line(points, stroke)]
+    } else if flags.contains(cell::Flags::DOTTED_UNDERLINE) {
+        Shape::dotted_line(
+            &underline,
+            color,
+            line_width * 2.0,
+            line_width / 2.0,
+        )
+    } else if flags.contains(cell::Flags::DASHED_UNDERLINE) {
+        Shape::dashed_line(&underline, stroke, period * 0.6, period * 0.4)
+    } else if flags.contains(cell::Flags::DOUBLE_UNDERLINE) {
+        let upper_y = underline_y - line_width * 2.0;
+        vec![
+            Shape::line_segment(underline, stroke),
+            Shape::line_segment(
+                [
+                    Pos2::new(cell_rect.min.x, upper_y),
+                    Pos2::new(cell_rect.max.x, upper_y),
+                ],
+                stroke,
+            ),
+        ]
+    } else {
+        vec![Shape::line_segment(underline, stroke)]
+    }
+}
+
+fn process_exit_overlay_event(
+    state: &mut TerminalViewState,
+    backend: &mut TerminalBackend,
+    event: &egui::Event,
+) {
+    if let egui::Event::Key {
+        key: Key::Enter,
+        pressed: true,
+        ..
+    } = event
+    {
+        state.respawn_error = backend
+            .respawn(true)
+            .err()
+            .map(|err| format!("Failed to restart: {}", err));
     }
 }
 
 fn process_keyboard_event(
     event: egui::Event,
+    state: &mut TerminalViewState,
     backend: &TerminalBackend,
     bindings_layout: &BindingsLayout,
     modifiers: Modifiers,
+    is_paste_requested: bool,
 ) -> InputAction {
+    if !backend.last_content().hint_labels.is_empty() {
+        return process_hint_event(event);
+    }
+
+    if backend.last_content().vi_search_prompt.is_some() {
+        return process_vi_search_event(event);
+    }
+
     match event {
         egui::Event::Text(text) => {
             process_text_event(&text, modifiers, backend, bindings_layout)
         },
-        egui::Event::Paste(text) => InputAction::BackendCall(
-            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-            if modifiers.contains(Modifiers::COMMAND | Modifiers::SHIFT) {
-                BackendCommand::Write(text.as_bytes().to_vec())
-            } else {
-                // Hotfix - Send ^V when there's not selection on view.
-                BackendCommand::Write([0x16].to_vec())
-            },
-            #[cfg(any(target_os = "ios", target_os = "macos"))]
-            {
-                BackendCommand::Write(text.as_bytes().to_vec())
-            },
-        ),
-        egui::Event::Copy => {
-            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-            if modifiers.contains(Modifiers::COMMAND | Modifiers::SHIFT) {
-                let content = backend.selectable_content();
-                InputAction::WriteToClipboard(content)
-            } else {
-                // Hotfix - Send ^C when there's not selection on view.
-                InputAction::BackendCall(BackendCommand::Write([0x3].to_vec()))
-            }
-            #[cfg(any(target_os = "ios", target_os = "macos"))]
-            {
-                let content = backend.selectable_content();
-                InputAction::WriteToClipboard(content)
-            }
+        egui::Event::Paste(text) if is_paste_requested => {
+            InputAction::BackendCall(BackendCommand::Paste(text))
+        },
+        egui::Event::Copy | egui::Event::Cut | egui::Event::Paste(_) => {
+            process_clipboard_event(
+                event,
+                state,
+                backend,
+                bindings_layout,
+                modifiers,
+            )
         },
         egui::Event::Key {
             key,
@@ -386,6 +940,7 @@ fn process_keyboard_event(
             modifiers,
             ..
         } => process_keyboard_key(
+            state,
             backend,
             bindings_layout,
             key,
@@ -396,12 +951,138 @@ fn process_keyboard_event(
     }
 }
 
+/// egui-winit turns clipboard shortcuts into Copy, Cut and Paste events
+/// without the key that was pressed, so the bindings of every key that can
+/// produce the event are looked up. A key bound to the clipboard action of
+/// the event wins when there is something to act on: on Windows, where
+/// Ctrl+C and Ctrl+Insert are reported alike, a selection is copied and
+/// ^C is sent otherwise.
+fn process_clipboard_event(
+    event: egui::Event,
+    state: &mut TerminalViewState,
+    backend: &TerminalBackend,
+    bindings_layout: &BindingsLayout,
+    modifiers: Modifiers,
+) -> InputAction {
+    let keys = clipboard_event_keys(&event, modifiers);
+    let terminal_mode = backend.last_content().terminal_mode;
+    let actions: Vec<_> = keys
+        .iter()
+        .map(|key| {
+            bindings_layout.get_action(
+                InputKind::KeyCode(*key),
+                modifiers,
+                terminal_mode,
+            )
+        })
+        .collect();
+
+    match &event {
+        egui::Event::Copy if actions.contains(&BindingAction::Copy) => {
+            if let action @ InputAction::WriteToClipboard(_) =
+                copy_selection(backend)
+            {
+                return action;
+            }
+        },
+        egui::Event::Paste(text) if actions.contains(&BindingAction::Paste) => {
+            return InputAction::BackendCall(BackendCommand::Paste(
+                text.clone(),
+            ));
+        },
+        _ => {},
+    }
+
+    let bound_key = keys
+        .into_iter()
+        .zip(actions)
+        .find(|(_, action)| *action != BindingAction::Ignore);
+    match (bound_key, event) {
+        (Some((key, _)), _) => process_keyboard_key(
+            state,
+            backend,
+            bindings_layout,
+            key,
+            modifiers,
+            true,
+        ),
+        (None, egui::Event::Copy) => copy_selection(backend),
+        (None, egui::Event::Paste(text)) => {
+            InputAction::BackendCall(BackendCommand::Paste(text))
+        },
+        (None, _) => InputAction::Ignore,
+    }
+}
+
+/// Keys that egui-winit reports as the given clipboard event, following
+/// its `is_copy_command`, `is_cut_command` and `is_paste_command`.
+fn clipboard_event_keys(event: &egui::Event, modifiers: Modifiers) -> Vec<Key> {
+    let (key, letter, windows_key, has_windows_modifier) = match event {
+        egui::Event::Copy => (Key::Copy, Key::C, Key::Insert, modifiers.ctrl),
+        egui::Event::Cut => (Key::Cut, Key::X, Key::Delete, modifiers.shift),
+        egui::Event::Paste(_) => {
+            (Key::Paste, Key::V, Key::Insert, modifiers.shift)
+        },
+        _ => return vec![],
+    };
+
+    let mut keys = vec![];
+    if modifiers.command {
+        keys.push(letter);
+    }
+    if cfg!(target_os = "windows") && has_windows_modifier {
+        keys.push(windows_key);
+    }
+    keys.push(key);
+    keys
+}
+
+/// Keyboard input goes to the search prompt while it is open in vi mode.
+fn process_vi_search_event(event: egui::Event) -> InputAction {
+    let command = match event {
+        egui::Event::Text(text) => BackendCommand::ViSearchInput(text),
+        egui::Event::Paste(text) => {
+            BackendCommand::ViSearchInput(text.replace(['\r', '\n'], ""))
+        },
+        egui::Event::Key {
+            key, pressed: true, ..
+        } => match key {
+            Key::Enter => BackendCommand::ViSearchConfirm,
+            Key::Escape => BackendCommand::ViSearchCancel,
+            Key::Backspace => BackendCommand::ViSearchBackspace,
+            _ => return InputAction::Ignore,
+        },
+        _ => return InputAction::Ignore,
+    };
+
+    InputAction::BackendCall(command)
+}
+
+/// Keyboard input goes to hint label selection while hints are shown.
+fn process_hint_event(event: egui::Event) -> InputAction {
+    match event {
+        egui::Event::Text(text) => {
+            InputAction::BackendCall(BackendCommand::HintInput(text))
+        },
+        egui::Event::Key {
+            key: Key::Escape,
+            pressed: true,
+            ..
+        } => InputAction::BackendCall(BackendCommand::StopHint),
+        _ => InputAction::Ignore,
+    }
+}
+
 fn process_text_event(
     text: &str,
     modifiers: Modifiers,
     backend: &TerminalBackend,
     bindings_layout: &BindingsLayout,
 ) -> InputAction {
+    if backend.last_content().terminal_mode.contains(TermMode::VI) {
+        return InputAction::Ignore;
+    }
+
     if let Some(key) = Key::from_name(text) {
         if bindings_layout.get_action(
             InputKind::KeyCode(key),
@@ -423,6 +1104,7 @@ fn process_text_event(
 }
 
 fn process_keyboard_key(
+    state: &mut TerminalViewState,
     backend: &TerminalBackend,
     bindings_layout: &BindingsLayout,
     key: Key,
@@ -439,8 +1121,16 @@ fn process_keyboard_key(
         modifiers,
         terminal_mode,
     );
+    let is_vi_scroll_to_top_pending =
+        std::mem::take(&mut state.is_vi_scroll_to_top_pending);
 
     match binding_action {
+        // Input meant for the PTY is dropped while vi mode is active.
+        BindingAction::Char(_) | BindingAction::Esc(_)
+            if terminal_mode.contains(TermMode::VI) =>
+        {
+            InputAction::Ignore
+        },
         BindingAction::Char(c) => {
             let mut buf = [0, 0, 0, 0];
             let str = c.encode_utf8(&mut buf);
@@ -451,32 +1141,84 @@ fn process_keyboard_key(
         BindingAction::Esc(seq) => InputAction::BackendCall(
             BackendCommand::Write(seq.as_bytes().to_vec()),
         ),
+        BindingAction::Copy => copy_selection(backend),
+        BindingAction::Paste => InputAction::RequestPaste,
+        BindingAction::ToggleViMode => {
+            InputAction::BackendCall(BackendCommand::ToggleViMode)
+        },
+        BindingAction::ViMotion(motion) => {
+            InputAction::BackendCall(BackendCommand::ViMotion(motion))
+        },
+        BindingAction::ViAction(ViAction::ScrollToTop)
+            if !is_vi_scroll_to_top_pending =>
+        {
+            state.is_vi_scroll_to_top_pending = true;
+            InputAction::Ignore
+        },
+        BindingAction::ViAction(action) => {
+            InputAction::BackendCall(BackendCommand::ViAction(action))
+        },
+        BindingAction::Hint(hint) => {
+            InputAction::BackendCall(BackendCommand::StartHint(hint))
+        },
         _ => InputAction::Ignore,
     }
 }
 
+fn copy_selection(backend: &TerminalBackend) -> InputAction {
+    let content = backend.selectable_content();
+    if content.is_empty() {
+        InputAction::Ignore
+    } else {
+        InputAction::WriteToClipboard(content)
+    }
+}
+
 fn process_mouse_wheel(
     state: &mut TerminalViewState,
+    backend: &TerminalBackend,
     font_size: f32,
     unit: MouseWheelUnit,
     delta: Vec2,
-) -> InputAction {
-    match unit {
+    modifiers: &Modifiers,
+) -> Vec<InputAction> {
+    let lines = match unit {
         MouseWheelUnit::Line => {
-            let lines = delta.y.signum() * delta.y.abs().ceil();
-            InputAction::BackendCall(BackendCommand::Scroll(lines as i32))
+            (delta.y.signum() * delta.y.abs().ceil()) as i32
         },
         MouseWheelUnit::Point => {
             state.scroll_pixels -= delta.y;
             let lines = (state.scroll_pixels / font_size).trunc();
             state.scroll_pixels %= font_size;
-            if lines != 0.0 {
-                InputAction::BackendCall(BackendCommand::Scroll(-lines as i32))
-            } else {
-                InputAction::Ignore
-            }
+            -lines as i32
         },
-        MouseWheelUnit::Page => InputAction::Ignore,
+        MouseWheelUnit::Page => 0,
+    };
+
+    if lines == 0 {
+        return vec![];
+    }
+
+    let terminal_mode = backend.last_content().terminal_mode;
+    if terminal_mode.intersects(TermMode::MOUSE_MODE) {
+        let button = if lines > 0 {
+            MouseButton::ScrollUp
+        } else {
+            MouseButton::ScrollDown
+        };
+
+        (0..lines.abs())
+            .map(|_| {
+                InputAction::BackendCall(BackendCommand::MouseReport(
+                    button,
+                    *modifiers,
+                    state.current_mouse_position_on_grid,
+                    true,
+                ))
+            })
+            .collect()
+    } else {
+        vec![InputAction::BackendCall(BackendCommand::Scroll(lines))]
     }
 }
 
@@ -490,6 +1232,11 @@ fn process_button_click(
     modifiers: &Modifiers,
     pressed: bool,
 ) -> InputAction {
+    let terminal_mode = backend.last_content().terminal_mode;
+    if terminal_mode.intersects(TermMode::MOUSE_MODE) {
+        return process_mouse_report_button(state, button, modifiers, pressed);
+    }
+
     match button {
         PointerButton::Primary => process_left_button(
             state,
@@ -504,6 +1251,28 @@ fn process_button_click(
     }
 }
 
+fn process_mouse_report_button(
+    state: &mut TerminalViewState,
+    button: PointerButton,
+    modifiers: &Modifiers,
+    pressed: bool,
+) -> InputAction {
+    let report_button = match button {
+        PointerButton::Primary => MouseButton::LeftButton,
+        PointerButton::Middle => MouseButton::MiddleButton,
+        PointerButton::Secondary => MouseButton::RightButton,
+        _ => return InputAction::Ignore,
+    };
+
+    state.mouse_report_button = if pressed { Some(button) } else { None };
+    InputAction::BackendCall(BackendCommand::MouseReport(
+        report_button,
+        *modifiers,
+        state.current_mouse_position_on_grid,
+        pressed,
+    ))
+}
+
 fn process_left_button(
     state: &mut TerminalViewState,
     layout: &Response,
@@ -513,15 +1282,7 @@ fn process_left_button(
     modifiers: &Modifiers,
     pressed: bool,
 ) -> InputAction {
-    let terminal_mode = backend.last_content().terminal_mode;
-    if terminal_mode.intersects(TermMode::MOUSE_MODE) {
-        InputAction::BackendCall(BackendCommand::MouseReport(
-            MouseButton::LeftButton,
-            *modifiers,
-            state.current_mouse_position_on_grid,
-            pressed,
-        ))
-    } else if pressed {
+    if pressed {
         process_left_button_pressed(state, layout, position)
     } else {
         process_left_button_released(
@@ -603,33 +1364,53 @@ fn process_mouse_move(
     let terminal_content = backend.last_content();
     let cursor_x = position.x - layout.rect.min.x;
     let cursor_y = position.y - layout.rect.min.y;
+    let previous_position = state.current_mouse_position_on_grid;
     state.current_mouse_position_on_grid = TerminalBackend::selection_point(
         cursor_x,
         cursor_y,
         &terminal_content.terminal_size,
-        terminal_content.grid.display_offset(),
+        terminal_content.display_offset,
     );
 
     let mut actions = vec![];
     // Handle command or selection update based on terminal mode and modifiers
-    if state.is_dragged {
-        let terminal_mode = terminal_content.terminal_mode;
-        let cmd = if terminal_mode.contains(TermMode::MOUSE_MOTION)
-            && modifiers.is_none()
-        {
-            InputAction::BackendCall(BackendCommand::MouseReport(
-                MouseButton::LeftMove,
-                *modifiers,
-                state.current_mouse_position_on_grid,
-                true,
-            ))
-        } else {
-            InputAction::BackendCall(BackendCommand::SelectUpdate(
-                cursor_x, cursor_y,
-            ))
+    let terminal_mode = terminal_content.terminal_mode;
+    if terminal_mode.intersects(TermMode::MOUSE_MODE) {
+        // Motion is reported once per cell, with the held button if any.
+        let move_button = match state.mouse_report_button {
+            Some(_)
+                if !terminal_mode.intersects(
+                    TermMode::MOUSE_DRAG | TermMode::MOUSE_MOTION,
+                ) =>
+            {
+                None
+            },
+            Some(PointerButton::Primary) => Some(MouseButton::LeftMove),
+            Some(PointerButton::Middle) => Some(MouseButton::MiddleMove),
+            Some(PointerButton::Secondary) => Some(MouseButton::RightMove),
+            Some(_) => None,
+            None if terminal_mode.contains(TermMode::MOUSE_MOTION) => {
+                Some(MouseButton::NoneMove)
+            },
+            None => None,
         };
 
-        actions.push(cmd);
+        if let Some(button) = move_button {
+            if previous_position != state.current_mouse_position_on_grid {
+                actions.push(InputAction::BackendCall(
+                    BackendCommand::MouseReport(
+                        button,
+                        *modifiers,
+                        state.current_mouse_position_on_grid,
+                        true,
+                    ),
+                ));
+            }
+        }
+    } else if state.is_dragged {
+        actions.push(InputAction::BackendCall(BackendCommand::SelectUpdate(
+            cursor_x, cursor_y,
+        )));
     }
 
     // Handle link hover if applicable